@@ -20,12 +20,13 @@ pkgs.mkShell {
     # asset authoring
     pkgs.blender
     pkgs.tiled
-    pkgs.jq
     pkgs.entr
     pkgs.just
     # wgpu C dependencies
     pkgs.pkgconfig
     pkgs.xorg.libX11
+    # gilrs (gamepad input)
+    pkgs.udev
   ];
   # make C libraries available
   LD_LIBRARY_PATH = with pkgs.xorg; with pkgs.lib.strings;
@@ -0,0 +1,96 @@
+//! Headless benchmarks, run with `cargo run --release -- bench-<name>`.
+
+use std::time::{Duration, Instant};
+
+use starframe as sf;
+
+use crate::{
+    collision_layers,
+    fire::{self, FireSystem, Flammable},
+};
+
+const DT: f64 = 1.0 / 60.0;
+const MAX_TICKS: usize = 10_000;
+
+/// Burn a square grid of flammable particles, each row linked like a vine,
+/// starting from one corner, and report how long the fire ticks take.
+/// Optional argument: number of particles per side of the grid (default 64).
+/// Returns the process exit code.
+pub fn bench_fire(args: &[String]) -> i32 {
+    let size: usize = match args.first().map(|arg| arg.parse()) {
+        None => 64,
+        Some(Ok(size)) if size > 0 => size,
+        Some(_) => {
+            eprintln!("usage: bench-fire [grid size]");
+            return 1;
+        }
+    };
+
+    let mut physics = sf::PhysicsWorld::new(
+        sf::physics::TuningConstants {
+            ..Default::default()
+        },
+        collision_layers::create_layer_matrix(),
+    );
+    let mut world = sf::hecs::World::new();
+    let mut hecs_sync = sf::HecsSyncManager::new_autosync(sf::HecsSyncOptions::both_ways());
+    let mut fire = FireSystem::new();
+
+    // spaced so that neighbors are within fire spread range
+    let spacing = 0.25;
+    let radius = 0.1;
+    let mut first = None;
+    for y in 0..size {
+        let mut prev_in_row = None;
+        for x in 0..size {
+            let pose = sf::Pose::new(
+                sf::Vec2::new(x as f64 * spacing, y as f64 * spacing),
+                sf::Angle::Rad(0.0).into(),
+            );
+            let coll_key = physics
+                .entity_set
+                .insert_collider(sf::Collider::new_circle(radius));
+            let entity = world.spawn((pose, coll_key, Flammable::default()));
+            if let Some(prev) = prev_in_row {
+                fire::link(&mut world, prev, entity);
+            }
+            prev_in_row = Some(entity);
+            first.get_or_insert(entity);
+        }
+    }
+    if let Some(first) = first {
+        if let Ok(mut flammable) = world.get::<&mut Flammable>(first) {
+            flammable.ignite();
+        }
+    }
+
+    let grav = sf::forcefield::Gravity(sf::Vec2::zero());
+    let mut tick_times: Vec<Duration> = Vec::new();
+    for _ in 0..MAX_TICKS {
+        hecs_sync.sync_hecs_to_physics(&mut physics, &mut world);
+        physics.tick(DT, None, &grav);
+        hecs_sync.sync_physics_to_hecs(&physics, &mut world);
+
+        let start = Instant::now();
+        fire.tick(DT, &mut physics, &mut world, &mut hecs_sync);
+        tick_times.push(start.elapsed());
+
+        if fire.active_count() == 0 {
+            break;
+        }
+    }
+
+    let remaining = world.query_mut::<&Flammable>().into_iter().count();
+    let total: Duration = tick_times.iter().sum();
+    let max = tick_times.iter().max().copied().unwrap_or_default();
+    println!("particles: {}", size * size);
+    println!("burned:    {}", size * size - remaining);
+    println!("ticks:     {}", tick_times.len());
+    println!("total:     {:.2} ms", total.as_secs_f64() * 1000.0);
+    println!(
+        "mean:      {:.1} µs",
+        total.as_secs_f64() * 1e6 / tick_times.len() as f64
+    );
+    println!("max:       {:.1} µs", max.as_secs_f64() * 1e6);
+    0
+}
@@ -0,0 +1,246 @@
+//! Embers, smoke and ash thrown off by fire.
+//!
+//! Embers are small physics particles that heat up what they land on,
+//! letting fire jump across gaps. Smoke and ash are purely visual.
+
+use starframe as sf;
+
+use crate::{
+    collision_layers,
+    fire::{FireSystem, Flammable},
+};
+
+/// Embers emitted per second per unit of `burning_heat`.
+const EMBERS_PER_HEAT: f64 = 0.005;
+const EMBER_RADIUS: f64 = 0.04;
+const EMBER_MASS: f64 = 0.01;
+const EMBER_LIFETIME: f64 = 2.5;
+/// Embers don't react to contacts right after being emitted
+/// so they don't land on the thing that emitted them.
+const EMBER_GRACE_TIME: f64 = 0.15;
+/// Heat given to a flammable thing an ember lands on.
+const EMBER_HEAT: f64 = 12.0;
+const EMBER_SPEED: (f64, f64) = (1.5, 4.0);
+const EMBER_COLOR: [f32; 4] = [1.0, 0.6, 0.1, 1.0];
+
+const SMOKE_PER_SECOND: f64 = 4.0;
+const SMOKE_COLOR: [f32; 4] = [0.35, 0.33, 0.32, 0.5];
+const ASH_PER_BURST: usize = 6;
+const ASH_COLOR: [f32; 4] = [0.15, 0.13, 0.12, 1.0];
+
+/// Physical ember that heats up flammable things it touches.
+#[derive(Clone, Copy, Debug)]
+pub struct Ember {
+    age: f64,
+}
+
+/// Visual-only particle moved without the physics engine.
+#[derive(Clone, Copy, Debug)]
+pub struct VisualParticle {
+    velocity: sf::Vec2,
+    /// Downward acceleration, negative to rise.
+    gravity: f64,
+    age: f64,
+    lifetime: f64,
+    radius: f64,
+    color: [f32; 4],
+    // how many size steps the current mesh has shrunk by
+    shown_step: u8,
+}
+
+/// Particles shrink in this many steps over their lifetime.
+const SHRINK_STEPS: f64 = 4.0;
+
+pub struct Embers {
+    rng: Rng,
+    // buffers reused every tick
+    ember_spawns: Vec<(sf::Pose, sf::Vec2)>,
+    visual_spawns: Vec<(sf::Pose, VisualParticle)>,
+    landings: Vec<(sf::hecs::Entity, Option<sf::hecs::Entity>)>,
+    to_despawn: Vec<sf::hecs::Entity>,
+}
+
+impl Embers {
+    pub fn new() -> Self {
+        Self {
+            rng: Rng(0x2545_f491_4f6c_dd1d),
+            ember_spawns: Vec::new(),
+            visual_spawns: Vec::new(),
+            landings: Vec::new(),
+            to_despawn: Vec::new(),
+        }
+    }
+
+    /// Emit new particles from fire and update existing ones.
+    /// Call after `FireSystem::tick` so that things burned away during it
+    /// turn into ash.
+    pub fn tick(
+        &mut self,
+        dt: f64,
+        fire: &mut FireSystem,
+        physics: &mut sf::PhysicsWorld,
+        world: &mut sf::hecs::World,
+        hecs_sync: &sf::HecsSyncManager,
+    ) {
+        self.emit(dt, fire, world);
+        self.spawn_emitted(physics, world);
+        self.update_embers(dt, fire, physics, world, hecs_sync);
+        self.update_visual_particles(dt, world);
+    }
+
+    fn emit(&mut self, dt: f64, fire: &FireSystem, world: &sf::hecs::World) {
+        for entity in fire.active() {
+            let Ok(mut query) = world.query_one::<(&Flammable, &sf::Pose)>(entity) else { continue };
+            let Some((flammable, pose)) = query.get() else { continue };
+            if !flammable.is_burning() {
+                continue;
+            }
+
+            let ember_chance = flammable.params().burning_heat * EMBERS_PER_HEAT * dt;
+            if self.rng.chance(ember_chance) {
+                // mostly upwards, like sparks rising from a fire
+                let angle = self.rng.range(-1.0, 1.0) + std::f64::consts::FRAC_PI_2;
+                let speed = self.rng.range(EMBER_SPEED.0, EMBER_SPEED.1);
+                let velocity = speed * sf::Vec2::new(angle.cos(), angle.sin());
+                self.ember_spawns.push((*pose, velocity));
+            }
+            if self.rng.chance(SMOKE_PER_SECOND * dt) {
+                let particle = VisualParticle {
+                    velocity: sf::Vec2::new(self.rng.range(-0.3, 0.3), 0.5),
+                    gravity: -0.5,
+                    age: 0.0,
+                    lifetime: self.rng.range(1.0, 2.0),
+                    radius: 0.12,
+                    color: SMOKE_COLOR,
+                    shown_step: 0,
+                };
+                self.visual_spawns.push((*pose, particle));
+            }
+        }
+
+        for pose in fire.burned_away() {
+            for _ in 0..ASH_PER_BURST {
+                let particle = VisualParticle {
+                    velocity: sf::Vec2::new(self.rng.range(-1.0, 1.0), self.rng.range(0.0, 1.5)),
+                    gravity: 3.0,
+                    age: 0.0,
+                    lifetime: self.rng.range(0.6, 1.2),
+                    radius: 0.05,
+                    color: ASH_COLOR,
+                    shown_step: 0,
+                };
+                self.visual_spawns.push((*pose, particle));
+            }
+        }
+    }
+
+    fn spawn_emitted(&mut self, physics: &mut sf::PhysicsWorld, world: &mut sf::hecs::World) {
+        for (pose, velocity) in self.ember_spawns.drain(..) {
+            let mut body = sf::Body::new_particle(EMBER_MASS);
+            body.velocity.linear = velocity;
+            let body_key = physics.entity_set.insert_body(body);
+            let coll = sf::Collider::new_circle(EMBER_RADIUS).with_layer(collision_layers::EMBER);
+            let coll_key = physics.entity_set.attach_collider(body_key, coll);
+            let mesh = circle_mesh(EMBER_RADIUS, EMBER_COLOR);
+            world.spawn((pose, body_key, coll_key, mesh, Ember { age: 0.0 }));
+        }
+        for (pose, particle) in self.visual_spawns.drain(..) {
+            let mesh = circle_mesh(particle.radius, particle.color);
+            world.spawn((pose, mesh, particle));
+        }
+    }
+
+    fn update_embers(
+        &mut self,
+        dt: f64,
+        fire: &mut FireSystem,
+        physics: &sf::PhysicsWorld,
+        world: &mut sf::hecs::World,
+        hecs_sync: &sf::HecsSyncManager,
+    ) {
+        for (entity, (ember, &coll_key)) in world.query_mut::<(&mut Ember, &sf::ColliderKey)>() {
+            ember.age += dt;
+            if ember.age >= EMBER_LIFETIME {
+                self.to_despawn.push(entity);
+                continue;
+            }
+            if ember.age < EMBER_GRACE_TIME {
+                continue;
+            }
+            if let Some(contact) = physics.contacts_for_collider(coll_key).next() {
+                let [a, b] = contact.colliders;
+                let other = hecs_sync.get_collider_entity(if a == coll_key { b } else { a });
+                self.landings.push((entity, other));
+            }
+        }
+
+        for (ember, other) in self.landings.drain(..) {
+            let other_flammable = other.and_then(|other| {
+                let is_burning = world.get::<&Flammable>(other).ok()?.is_burning();
+                Some((other, is_burning))
+            });
+            match other_flammable {
+                // bounce off things that are already burning
+                Some((_, true)) => continue,
+                Some((other, false)) => fire.add_heat(other, EMBER_HEAT),
+                None => {}
+            }
+            self.to_despawn.push(ember);
+        }
+
+        for entity in self.to_despawn.drain(..) {
+            world.despawn(entity).ok();
+        }
+    }
+
+    fn update_visual_particles(&mut self, dt: f64, world: &mut sf::hecs::World) {
+        for (entity, (pose, mesh, particle)) in
+            world.query_mut::<(&mut sf::Pose, &mut sf::Mesh, &mut VisualParticle)>()
+        {
+            particle.age += dt;
+            if particle.age >= particle.lifetime {
+                self.to_despawn.push(entity);
+                continue;
+            }
+            particle.velocity.y -= particle.gravity * dt;
+            pose.translation += particle.velocity * dt;
+
+            let step = (particle.age / particle.lifetime * SHRINK_STEPS) as u8;
+            if step != particle.shown_step {
+                particle.shown_step = step;
+                let scale = 1.0 - step as f64 / SHRINK_STEPS;
+                *mesh = circle_mesh(particle.radius * scale, particle.color);
+            }
+        }
+
+        for entity in self.to_despawn.drain(..) {
+            world.despawn(entity).ok();
+        }
+    }
+}
+
+fn circle_mesh(r: f64, color: [f32; 4]) -> sf::Mesh {
+    sf::Mesh::from(sf::ConvexMeshShape::Circle { r, points: 6 }).with_color(color)
+}
+
+/// Small xorshift random number generator, good enough for scattering particles.
+struct Rng(u64);
+
+impl Rng {
+    /// Random number between 0 and 1.
+    fn next(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        min + (max - min) * self.next()
+    }
+
+    /// True with the given probability.
+    fn chance(&mut self, probability: f64) -> bool {
+        self.next() < probability
+    }
+}
@@ -1,14 +1,30 @@
 //! Logic for propagating fire and having it destroy things.
 
+use std::collections::{HashMap, HashSet};
+
+use assets_manager::{loader, Asset};
 use starframe as sf;
 
+mod sources;
+pub use sources::{
+    heat_source_mesh, HeatSource, Lens, PressureSwitch, TimedIgniter, Torch, HEAT_SOURCE_RADIUS,
+};
+
 const FIRE_SPREAD_RANGE: f64 = 0.2;
+/// Ignition temperature is multiplied by up to this much plus one when completely wet.
+const WETNESS_IGNITION_FACTOR: f64 = 4.0;
+/// Wetness lost per second while being heated.
+const DRYING_RATE: f64 = 0.25;
+/// Thickness of the shape used to check for insulators between burning things.
+const INSULATION_CHECK_RADIUS: f64 = 0.05;
 
 /// Component that marks things as able to catch fire.
 #[derive(Clone, Copy, Debug)]
 pub struct Flammable {
     params: FlammableParams,
     state: FlammableState,
+    // from 0 (dry) to 1 (soaked), makes it harder to catch fire
+    wetness: f64,
 }
 impl Default for Flammable {
     fn default() -> Self {
@@ -21,6 +37,7 @@ impl Flammable {
         Self {
             params,
             state: FlammableState::default(),
+            wetness: 0.0,
         }
     }
 
@@ -34,6 +51,154 @@ impl Flammable {
         self.ignite();
         self
     }
+
+    /// Put out the fire if burning and cool down completely.
+    #[inline]
+    pub fn extinguish(&mut self) {
+        self.state = FlammableState::default();
+    }
+
+    #[inline]
+    pub fn params(&self) -> &FlammableParams {
+        &self.params
+    }
+
+    #[inline]
+    pub fn is_burning(&self) -> bool {
+        matches!(self.state, FlammableState::OnFire { .. })
+    }
+
+    /// Make this wetter, up to completely soaked.
+    #[inline]
+    pub fn wet(&mut self, amount: f64) {
+        self.wetness = (self.wetness + amount).min(1.0);
+    }
+
+    /// Temperature needed to catch fire, higher the wetter this is.
+    #[inline]
+    pub fn ignition_temperature(&self) -> f64 {
+        self.params.temp_to_catch_fire * (1.0 + self.wetness * WETNESS_IGNITION_FACTOR)
+    }
+
+    /// How close this is to catching fire, from 0 (cold) to 1 (burning).
+    pub fn temperature_fraction(&self) -> f64 {
+        match self.state {
+            FlammableState::OnFire { .. } => 1.0,
+            FlammableState::NotOnFire { temperature, .. } => {
+                (temperature / self.ignition_temperature()).clamp(0.0, 1.0)
+            }
+        }
+    }
+
+    /// How far along this is in burning away, from 0 to 1.
+    /// None if not burning, always 0 for things that burn forever.
+    pub fn burn_progress(&self) -> Option<f64> {
+        let FlammableState::OnFire { time_burning } = self.state else { return None };
+        Some(match self.params.time_to_destroy {
+            Some(time_to_destroy) => (time_burning / time_to_destroy).min(1.0),
+            None => 0.0,
+        })
+    }
+
+    /// Whether this needs to be updated by `FireSystem`.
+    #[inline]
+    fn is_active(&self) -> bool {
+        match self.state {
+            FlammableState::OnFire { .. } => true,
+            FlammableState::NotOnFire { temperature, .. } => temperature > 0.0,
+        }
+    }
+}
+
+/// Component listing entities that fire spreads to from this one regardless of distance,
+/// e.g. neighboring vine particles and whatever the end of a vine is attached to.
+///
+/// Links are one-way, use `link` to connect two entities both ways.
+#[derive(Clone, Debug, Default)]
+pub struct FireLinks(pub Vec<sf::hecs::Entity>);
+
+/// Component listing the entities that make up one object together,
+/// e.g. the edges of a polygon. When one of them burns away, the rest go with it.
+///
+/// Use `burn_together` to set it up on every entity of the group.
+#[derive(Clone, Debug)]
+pub struct BurnGroup(pub Vec<sf::hecs::Entity>);
+
+/// Component for things that block heat from spreading through them,
+/// e.g. stone walls between two wooden structures.
+/// Doesn't block `FireLinks`.
+#[derive(Clone, Copy, Debug)]
+pub struct Insulator;
+
+/// Component for areas that make flammable things inside them wet.
+#[derive(Clone, Copy, Debug)]
+pub struct WetArea {
+    /// Shape of the area relative to the entity's pose.
+    /// Not inserted into physics, only used for queries.
+    pub collider: sf::Collider,
+    pub wetting: Wetting,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Wetting {
+    /// Puts out fires and soaks completely, like a pool of water.
+    Submerge,
+    /// Soaks gradually by this much per second, like mist or rain.
+    Dampen(f64),
+}
+
+/// Make fire spread between two entities in both directions.
+pub fn link(world: &mut sf::hecs::World, a: sf::hecs::Entity, b: sf::hecs::Entity) {
+    for (from, to) in [(a, b), (b, a)] {
+        if let Ok(mut links) = world.get::<&mut FireLinks>(from) {
+            links.0.push(to);
+            continue;
+        }
+        world.insert_one(from, FireLinks(vec![to])).ok();
+    }
+}
+
+/// Remove every link to and from an entity, e.g. before despawning it.
+pub fn unlink(world: &mut sf::hecs::World, entity: sf::hecs::Entity) {
+    let Ok(FireLinks(linked)) = world.remove_one::<FireLinks>(entity) else { return };
+    for other in linked {
+        if let Ok(mut links) = world.get::<&mut FireLinks>(other) {
+            links.0.retain(|&e| e != entity);
+        }
+    }
+}
+
+/// Make all of the given entities burn away when any one of them does.
+pub fn burn_together(world: &mut sf::hecs::World, entities: &[sf::hecs::Entity]) {
+    if entities.len() < 2 {
+        return;
+    }
+    for &entity in entities {
+        world.insert_one(entity, BurnGroup(entities.to_vec())).ok();
+    }
+}
+
+/// Add the rest of the burn groups of everything about to be destroyed to the list.
+fn add_burn_groups(
+    world: &sf::hecs::World,
+    to_destroy: &mut Vec<(sf::hecs::Entity, Option<BurntParams>)>,
+) {
+    let mut i = 0;
+    while i < to_destroy.len() {
+        let entity = to_destroy[i].0;
+        i += 1;
+        let Ok(group) = world.get::<&BurnGroup>(entity) else { continue };
+        for &other in &group.0 {
+            if !world.contains(other) || to_destroy.iter().any(|(e, _)| *e == other) {
+                continue;
+            }
+            let burnt = world
+                .get::<&Flammable>(other)
+                .ok()
+                .and_then(|f| f.params.burnt);
+            to_destroy.push((other, burnt));
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -56,7 +221,8 @@ impl Default for FlammableState {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(default)]
 pub struct FlammableParams {
     pub temp_to_catch_fire: f64,
     /// Time the object spends on fire before being destroyed.
@@ -66,6 +232,8 @@ pub struct FlammableParams {
     pub burning_heat: f64,
     /// Temperature of this decreases by this per second if nothing is burning nearby
     pub cooldown_rate: f64,
+    /// If set, turn into charred remains after `time_to_destroy` instead of disappearing
+    pub burnt: Option<BurntParams>,
 }
 impl Default for FlammableParams {
     fn default() -> Self {
@@ -74,95 +242,530 @@ impl Default for FlammableParams {
             time_to_destroy: Some(0.066),
             burning_heat: 300.0,
             cooldown_rate: 2.0,
+            burnt: None,
+        }
+    }
+}
+
+/// What's left of something after it's done burning.
+///
+/// Each collider turns into a separate non-flammable dynamic body,
+/// so burning part of a larger object breaks that part off
+/// and burning a static support makes it fall.
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[serde(default)]
+pub struct BurntParams {
+    pub density: f64,
+    pub color: [f32; 4],
+}
+impl Default for BurntParams {
+    fn default() -> Self {
+        Self {
+            density: 0.1,
+            color: [0.1, 0.09, 0.08, 1.0],
         }
     }
 }
 
+/// Named sets of `FlammableParams` loaded from `assets/fire_materials.json`,
+/// so that things can be given different burn behavior from Tiled.
+/// Fields not given for a material use the defaults of `FlammableParams`.
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(transparent)]
+pub struct FireMaterials(HashMap<String, FlammableParams>);
+
+impl Asset for FireMaterials {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+impl FireMaterials {
+    pub fn get(&self, name: &str) -> Option<FlammableParams> {
+        self.0.get(name).copied()
+    }
+
+    /// Create a `Flammable` from the named material,
+    /// falling back to default parameters if it doesn't exist.
+    /// Scene validation warns about unknown materials.
+    pub fn flammable(&self, name: &str) -> Flammable {
+        Flammable::new(self.get(name).unwrap_or_default())
+    }
+}
+
 //
 // tick
 //
 
-pub fn tick(
-    dt: f64,
-    physics: &mut sf::PhysicsWorld,
-    world: &mut sf::hecs::World,
-    hecs_sync: &mut sf::HecsSyncManager,
-) {
-    // reset cooling down state
+/// Neighbors are queried again after this many seconds
+/// in case things have moved.
+const NEIGHBOR_REFRESH_INTERVAL: f64 = 0.2;
 
-    for (_, flammable) in world.query_mut::<&mut Flammable>() {
-        if let FlammableState::NotOnFire {
-            ref mut cooling_down,
-            ..
-        } = flammable.state
-        {
-            *cooling_down = true;
+/// State for updating fire, kept between ticks to avoid repeating work.
+///
+/// Only entities that are burning or warm (the "active" ones) are updated,
+/// so flammables far away from any fire cost nothing.
+/// Things that are already burning when spawned are found by scanning everything,
+/// which happens on the first tick and after `reset`.
+pub struct FireSystem {
+    active: HashSet<sf::hecs::Entity>,
+    needs_rescan: bool,
+    // entities near each burning one, from the last shape query
+    neighbors: HashMap<sf::hecs::Entity, CachedNeighbors>,
+    // buffers reused every tick
+    delta_temps: Vec<(sf::hecs::Entity, f64)>,
+    to_destroy: Vec<(sf::hecs::Entity, Option<BurntParams>)>,
+    wetted: Vec<(sf::hecs::Entity, Wetting)>,
+    // ones that stopped being active since the last visual update,
+    // so their visuals can go back to normal
+    deactivated: Vec<sf::hecs::Entity>,
+    // total time ticked, for animating visuals
+    time: f64,
+    // where things burned away during the last tick
+    burned_away: Vec<sf::Pose>,
+}
+
+type HeatSourceQuery<'a> = (
+    &'a Flammable,
+    &'a sf::ColliderKey,
+    &'a sf::Pose,
+    Option<&'a FireLinks>,
+);
+
+#[derive(Default)]
+struct CachedNeighbors {
+    entities: Vec<sf::hecs::Entity>,
+    time_until_refresh: f64,
+}
+
+impl FireSystem {
+    pub fn new() -> Self {
+        Self {
+            active: HashSet::new(),
+            needs_rescan: true,
+            neighbors: HashMap::new(),
+            delta_temps: Vec::new(),
+            to_destroy: Vec::new(),
+            wetted: Vec::new(),
+            deactivated: Vec::new(),
+            time: 0.0,
+            burned_away: Vec::new(),
         }
     }
 
-    // heat up adjacent flammables
+    /// Forget everything about the current world.
+    /// Call when the world is cleared or replaced.
+    pub fn reset(&mut self) {
+        self.active.clear();
+        self.neighbors.clear();
+        self.needs_rescan = true;
+        // heat added after the last tick would otherwise land on recycled entity ids
+        self.delta_temps.clear();
+        self.to_destroy.clear();
+        self.wetted.clear();
+        self.deactivated.clear();
+        self.burned_away.clear();
+    }
 
-    // defer mutation to avoid nested mutable hecs queries
-    let mut delta_temps: Vec<(sf::hecs::Entity, f64)> = Vec::new();
-    for (_, (flammable, &coll_key, pose)) in
-        world.query_mut::<(&Flammable, &sf::ColliderKey, &sf::Pose)>()
-    {
-        let FlammableState::OnFire { .. } = flammable.state else { continue };
-        let Some(coll) = physics.entity_set.get_collider(coll_key) else { continue };
+    /// Number of entities currently burning or warm.
+    pub fn active_count(&self) -> usize {
+        self.active.len()
+    }
 
-        for (other_coll_key, _) in physics.query_shape(
-            *pose,
-            coll.shape.expanded(FIRE_SPREAD_RANGE),
-            Default::default(),
-        ) {
-            if other_coll_key == coll_key {
-                continue;
+    /// Entities currently burning or warm.
+    pub fn active(&self) -> impl Iterator<Item = sf::hecs::Entity> + '_ {
+        self.active.iter().copied()
+    }
+
+    /// Poses of things that burned away and were destroyed during the last tick.
+    pub fn burned_away(&self) -> &[sf::Pose] {
+        &self.burned_away
+    }
+
+    /// Heat up a flammable entity from something other than fire spreading.
+    /// Applied on the next tick.
+    pub fn add_heat(&mut self, entity: sf::hecs::Entity, amount: f64) {
+        self.delta_temps.push((entity, amount));
+    }
+
+    pub fn tick(
+        &mut self,
+        dt: f64,
+        physics: &mut sf::PhysicsWorld,
+        world: &mut sf::hecs::World,
+        hecs_sync: &mut sf::HecsSyncManager,
+    ) {
+        self.time += dt;
+        self.burned_away.clear();
+        let Self {
+            active,
+            needs_rescan,
+            neighbors,
+            delta_temps,
+            to_destroy,
+            wetted,
+            deactivated,
+            burned_away,
+            ..
+        } = self;
+
+        if *needs_rescan {
+            active.extend(
+                world
+                    .query_mut::<&Flammable>()
+                    .into_iter()
+                    .filter(|(_, flammable)| flammable.is_active())
+                    .map(|(entity, _)| entity),
+            );
+            *needs_rescan = false;
+        }
+
+        // wet things in water
+
+        for (_, (area, pose)) in world.query::<(&WetArea, &sf::Pose)>().iter() {
+            let touched = physics.query_shape(*pose, area.collider.shape, Default::default());
+            for (coll_key, _) in touched {
+                let Some(entity) = hecs_sync.get_collider_entity(coll_key) else { continue };
+                wetted.push((entity, area.wetting));
             }
-            let Some(other_entity) = hecs_sync.get_collider_entity(other_coll_key) else { continue };
-            // not checking if the other entity has a Flammable component here,
-            // we'll need to query for it in the next loop anyway so we can do the check there
-            delta_temps.push((other_entity, flammable.params.burning_heat * dt));
-        }
-    }
-    for (entity, delta_temp) in delta_temps {
-        let Ok(flammable) = world.query_one_mut::<&mut Flammable>(entity) else { continue };
-        if let FlammableState::NotOnFire {
-            temperature,
-            cooling_down,
-        } = &mut flammable.state
-        {
-            *temperature += delta_temp;
-            *cooling_down = false;
-        }
-    }
-
-    // cool down ones that weren't heated up,
-    // ignite ones that heated up enough,
-    // destroy ones that burned for long enough
-
-    let mut to_destroy: Vec<sf::hecs::Entity> = Vec::new();
-    for (entity, flammable) in world.query_mut::<&mut Flammable>() {
-        match &mut flammable.state {
-            FlammableState::OnFire { time_burning } => {
-                *time_burning += dt;
-                if *time_burning >= flammable.params.time_to_destroy.unwrap_or(f64::INFINITY) {
-                    to_destroy.push(entity);
+        }
+        for (entity, wetting) in wetted.drain(..) {
+            let Ok(flammable) = world.query_one_mut::<&mut Flammable>(entity) else { continue };
+            match wetting {
+                Wetting::Submerge => {
+                    flammable.wet(1.0);
+                    flammable.extinguish();
+                }
+                Wetting::Dampen(rate) => flammable.wet(rate * dt),
+            }
+        }
+
+        // heat from torches, lenses etc.
+
+        sources::collect_heat(dt, physics, world, hecs_sync, delta_temps);
+
+        // heat up things near and linked to burning ones
+
+        // defer mutation to avoid nested mutable hecs queries
+        for &entity in active.iter() {
+            let Ok(mut query) = world.query_one::<HeatSourceQuery>(entity) else { continue };
+            let Some((flammable, &coll_key, pose, links)) = query.get() else { continue };
+            let FlammableState::OnFire { .. } = flammable.state else { continue };
+            let heat = flammable.params.burning_heat * dt;
+
+            // linked things heat up no matter how far apart they drift.
+            // links to entities that have since been destroyed are skipped in the next loop
+            let links = links.map(|l| l.0.as_slice()).unwrap_or_default();
+            delta_temps.extend(links.iter().map(|&other_entity| (other_entity, heat)));
+
+            let cached = neighbors.entry(entity).or_default();
+            cached.time_until_refresh -= dt;
+            if cached.time_until_refresh <= 0.0 {
+                cached.time_until_refresh = NEIGHBOR_REFRESH_INTERVAL;
+                cached.entities.clear();
+                let Some(coll) = physics.entity_set.get_collider(coll_key) else { continue };
+                for (other_coll_key, _) in physics.query_shape(
+                    *pose,
+                    coll.shape.expanded(FIRE_SPREAD_RANGE),
+                    Default::default(),
+                ) {
+                    if other_coll_key == coll_key {
+                        continue;
+                    }
+                    let Some(other_entity) = hecs_sync.get_collider_entity(other_coll_key) else { continue };
+                    // already heated through the link
+                    if links.contains(&other_entity) {
+                        continue;
+                    }
+                    let Ok(other_pose) = world.get::<&sf::Pose>(other_entity) else { continue };
+                    if is_insulated(
+                        physics,
+                        hecs_sync,
+                        world,
+                        (entity, pose.translation),
+                        (other_entity, other_pose.translation),
+                    ) {
+                        continue;
+                    }
+                    cached.entities.push(other_entity);
                 }
             }
-            FlammableState::NotOnFire {
+            delta_temps.extend(
+                cached
+                    .entities
+                    .iter()
+                    .map(|&other_entity| (other_entity, heat)),
+            );
+        }
+        for (entity, delta_temp) in delta_temps.drain(..) {
+            // not checking if the other entity has a Flammable component when collecting,
+            // this query takes care of that
+            let Ok(flammable) = world.query_one_mut::<&mut Flammable>(entity) else { continue };
+            if let FlammableState::NotOnFire {
                 temperature,
                 cooling_down,
-            } => {
-                if *cooling_down {
-                    *temperature = (*temperature - flammable.params.cooldown_rate * dt).max(0.0);
-                } else if *temperature >= flammable.params.temp_to_catch_fire {
-                    flammable.ignite();
+            } = &mut flammable.state
+            {
+                *temperature += delta_temp;
+                *cooling_down = false;
+                active.insert(entity);
+            }
+        }
+
+        // cool down ones that weren't heated up,
+        // ignite ones that heated up enough,
+        // destroy ones that burned for long enough,
+        // and stop updating ones that have cooled down completely
+
+        active.retain(|&entity| {
+            let Ok(flammable) = world.query_one_mut::<&mut Flammable>(entity) else {
+                neighbors.remove(&entity);
+                return false;
+            };
+            let ignition_temperature = flammable.ignition_temperature();
+            match &mut flammable.state {
+                FlammableState::OnFire { time_burning } => {
+                    *time_burning += dt;
+                    if *time_burning >= flammable.params.time_to_destroy.unwrap_or(f64::INFINITY) {
+                        to_destroy.push((entity, flammable.params.burnt));
+                        neighbors.remove(&entity);
+                        return false;
+                    }
+                }
+                FlammableState::NotOnFire {
+                    temperature,
+                    cooling_down,
+                } => {
+                    if *cooling_down {
+                        *temperature =
+                            (*temperature - flammable.params.cooldown_rate * dt).max(0.0);
+                    } else if *temperature >= ignition_temperature {
+                        flammable.ignite();
+                        return true;
+                    } else {
+                        // heat dries things out
+                        flammable.wetness = (flammable.wetness - DRYING_RATE * dt).max(0.0);
+                    }
+                    // assume cooling down until heated again next tick
+                    *cooling_down = true;
+                }
+            }
+            let keep = flammable.is_active();
+            if !keep {
+                neighbors.remove(&entity);
+                deactivated.push(entity);
+            }
+            keep
+        });
+
+        add_burn_groups(world, to_destroy);
+        for (entity, burnt) in to_destroy.drain(..) {
+            if let Ok(pose) = world.get::<&sf::Pose>(entity) {
+                burned_away.push(*pose);
+            }
+            match burnt {
+                Some(burnt) => char_remains(entity, burnt, physics, world),
+                None => {
+                    world.despawn(entity).ok();
                 }
             }
         }
     }
 
-    for entity in to_destroy {
-        world.despawn(entity).ok();
+    /// Update the meshes of entities with `BurnVisuals` to match their fire state.
+    pub fn update_visuals(&mut self, world: &mut sf::hecs::World) {
+        let pulse = 0.5 + 0.5 * (std::f64::consts::TAU * PULSE_FREQUENCY * self.time).sin();
+        for entity in self.active.iter().chain(&self.deactivated) {
+            let Ok((flammable, visuals, mesh)) = world.query_one_mut::<VisualsQuery>(*entity) else { continue };
+            visuals.update(flammable, pulse, mesh);
+        }
+        self.deactivated.clear();
+    }
+}
+
+/// Check if there's an `Insulator` on the straight line between two entities,
+/// not counting the entities themselves.
+fn is_insulated(
+    physics: &sf::PhysicsWorld,
+    hecs_sync: &sf::HecsSyncManager,
+    world: &sf::hecs::World,
+    (from_entity, from): (sf::hecs::Entity, sf::Vec2),
+    (to_entity, to): (sf::hecs::Entity, sf::Vec2),
+) -> bool {
+    let dist = to - from;
+    let len = dist.mag();
+    if len <= f64::EPSILON {
+        return false;
+    }
+    let pose = sf::Pose::new(
+        (from + to) / 2.0,
+        sf::Angle::Rad(f64::atan2(dist.y, dist.x)).into(),
+    );
+    let shape = sf::Collider::new_capsule(len, INSULATION_CHECK_RADIUS).shape;
+    physics
+        .query_shape(pose, shape, Default::default())
+        .into_iter()
+        .filter_map(|(coll_key, _)| hecs_sync.get_collider_entity(coll_key))
+        .filter(|&entity| entity != from_entity && entity != to_entity)
+        .any(|entity| world.get::<&Insulator>(entity).is_ok())
+}
+
+type RemainsQuery<'a> = (&'a sf::Pose, &'a sf::ColliderKey, Option<&'a sf::BodyKey>);
+
+/// Replace a burned entity with a charred, non-flammable version of its collider.
+fn char_remains(
+    entity: sf::hecs::Entity,
+    burnt: BurntParams,
+    physics: &mut sf::PhysicsWorld,
+    world: &mut sf::hecs::World,
+) {
+    let Ok((&pose, &coll_key, body_key)) = world.query_one_mut::<RemainsQuery>(entity) else { return };
+    let Some(&coll) = physics.entity_set.get_collider(coll_key) else { return };
+    let velocity = body_key
+        .and_then(|&body_key| physics.entity_set.get_body(body_key))
+        .map(|body| body.velocity);
+    // removes the old collider (and body) from physics too
+    world.despawn(entity).ok();
+
+    let mut body = sf::Body::new_dynamic(coll.info(), burnt.density);
+    if let Some(velocity) = velocity {
+        body.velocity = velocity;
+    }
+    let body_key = physics.entity_set.insert_body(body);
+    let coll_key = physics.entity_set.attach_collider(body_key, coll);
+    let mesh = sf::Mesh::from(coll).with_color(burnt.color);
+    world.spawn((pose, body_key, coll_key, mesh));
+}
+
+//
+// visuals
+//
+
+/// Color things move towards as they heat up.
+const HEATED_COLOR: [f32; 3] = [1.0, 0.45, 0.1];
+/// Color burning things pulse towards from `HEATED_COLOR`.
+const BURNING_COLOR: [f32; 3] = [1.0, 0.85, 0.3];
+/// How much of `HEATED_COLOR` is mixed in right before catching fire.
+const MAX_HEAT_TINT: f64 = 0.7;
+const PULSE_FREQUENCY: f64 = 3.0;
+/// Size things shrink to right before burning away.
+const MIN_BURN_SCALE: f64 = 0.3;
+/// Tint and scale are rounded to this many steps
+/// so that meshes don't need to be regenerated every tick.
+const VISUAL_STEPS: f64 = 16.0;
+
+type VisualsQuery<'a> = (&'a Flammable, &'a mut BurnVisuals, &'a mut sf::Mesh);
+
+/// Component that makes the mesh of a `Flammable` entity show its state:
+/// tinted as it heats up, pulsing while burning, and shrinking as it burns away.
+pub struct BurnVisuals {
+    base_color: [f32; 4],
+    mesh_at_scale: Box<dyn Fn(f64) -> sf::Mesh + Send + Sync>,
+    // uncolored mesh at the shown scale step, kept around because generating it
+    // can be expensive (polygons are split into convex pieces again)
+    scaled_mesh: Option<(u8, sf::Mesh)>,
+    // rounded (burning, tint, scale) of the mesh currently shown
+    shown: (bool, u8, u8),
+}
+
+impl BurnVisuals {
+    /// `mesh_at_scale` generates the entity's mesh without color
+    /// at the given scale, 1.0 being the original size.
+    pub fn new(
+        base_color: [f32; 4],
+        mesh_at_scale: impl Fn(f64) -> sf::Mesh + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            base_color,
+            mesh_at_scale: Box::new(mesh_at_scale),
+            scaled_mesh: None,
+            shown: (false, 0, VISUAL_STEPS as u8),
+        }
+    }
+
+    fn update(&mut self, flammable: &Flammable, pulse: f64, mesh: &mut sf::Mesh) {
+        let (burning, tint, scale) = match flammable.burn_progress() {
+            Some(progress) => (true, pulse, 1.0 - progress * (1.0 - MIN_BURN_SCALE)),
+            None => (false, flammable.temperature_fraction() * MAX_HEAT_TINT, 1.0),
+        };
+        let rounded = (
+            burning,
+            (tint * VISUAL_STEPS).round() as u8,
+            (scale * VISUAL_STEPS).round() as u8,
+        );
+        if rounded == self.shown {
+            return;
+        }
+        self.shown = rounded;
+
+        let tint = rounded.1 as f32 / VISUAL_STEPS as f32;
+        let (from, to) = if burning {
+            (HEATED_COLOR, BURNING_COLOR)
+        } else {
+            let [r, g, b, _] = self.base_color;
+            ([r, g, b], HEATED_COLOR)
+        };
+        let mix = |i: usize| from[i] + (to[i] - from[i]) * tint;
+        let color = [mix(0), mix(1), mix(2), self.base_color[3]];
+
+        // the color pulses every tick while burning, only regenerate when the size changes
+        let scale_step = rounded.2;
+        if self.scaled_mesh.as_ref().map(|(step, _)| *step) != Some(scale_step) {
+            let scaled = (self.mesh_at_scale)(scale_step as f64 / VISUAL_STEPS);
+            self.scaled_mesh = Some((scale_step, scaled));
+        }
+        if let Some((_, scaled)) = &self.scaled_mesh {
+            *mesh = scaled.clone().with_color(color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links(world: &sf::hecs::World, entity: sf::hecs::Entity) -> Vec<sf::hecs::Entity> {
+        world
+            .get::<&FireLinks>(entity)
+            .map(|links| links.0.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn unlinking_removes_links_both_ways() {
+        let mut world = sf::hecs::World::new();
+        let [a, b, c] = [(); 3].map(|_| world.spawn(()));
+        link(&mut world, a, b);
+        link(&mut world, b, c);
+        assert_eq!(links(&world, b), vec![a, c]);
+
+        unlink(&mut world, c);
+        assert_eq!(links(&world, b), vec![a]);
+        assert!(links(&world, c).is_empty());
+        assert_eq!(links(&world, a), vec![b]);
+
+        // relinking after a pop doesn't leave duplicates behind
+        link(&mut world, b, c);
+        assert_eq!(links(&world, b), vec![a, c]);
+        assert_eq!(links(&world, c), vec![b]);
+    }
+
+    #[test]
+    fn burning_one_piece_destroys_the_whole_group() {
+        let mut world = sf::hecs::World::new();
+        let [a, b, c, unrelated] = [(); 4].map(|_| world.spawn(()));
+        burn_together(&mut world, &[a, b, c]);
+
+        let mut to_destroy = vec![(b, None)];
+        add_burn_groups(&world, &mut to_destroy);
+        let destroyed: Vec<_> = to_destroy.iter().map(|(e, _)| *e).collect();
+        assert_eq!(destroyed, vec![b, a, c]);
+        assert!(!destroyed.contains(&unrelated));
+
+        // pieces that already burned away on their own aren't destroyed twice
+        world.despawn(a).unwrap();
+        let mut to_destroy = vec![(c, None), (b, None)];
+        add_burn_groups(&world, &mut to_destroy);
+        assert_eq!(to_destroy.len(), 2);
     }
 }
@@ -0,0 +1,137 @@
+//! Things that heat up flammables from outside the fire simulation:
+//! torches, timed igniters, pressure switches and lenses.
+//!
+//! These are applied at the start of `FireSystem::tick`
+//! the same way as heat from burning things.
+
+use starframe as sf;
+
+use super::Flammable;
+use crate::scene::ObjectId;
+
+/// Radius of the visible part of torches and igniters.
+pub const HEAT_SOURCE_RADIUS: f64 = 0.2;
+
+/// Component that heats up flammable things within a radius of its pose.
+#[derive(Clone, Copy, Debug)]
+pub struct HeatSource {
+    /// Temperature added per second to everything in range.
+    pub heat: f64,
+    pub radius: f64,
+    pub is_active: bool,
+}
+
+/// Marker component for heat sources the player can pick up and carry around.
+#[derive(Clone, Copy, Debug)]
+pub struct Torch;
+
+/// Component that turns on the `HeatSource` on the same entity after a delay,
+/// and optionally turns it off again after burning for a while.
+#[derive(Clone, Copy, Debug)]
+pub struct TimedIgniter {
+    pub delay: f64,
+    /// How long the heat source stays on, forever if None.
+    pub duration: Option<f64>,
+    pub elapsed: f64,
+}
+
+/// Component for a plate that ignites flammable objects with the target's object id
+/// when a physics object is pushed onto it.
+///
+/// The switch's collider must be a sensor in the `PRESSURE_SWITCH` collision layer.
+#[derive(Clone, Copy, Debug)]
+pub struct PressureSwitch {
+    /// Tiled object id of the thing to ignite.
+    pub target: u32,
+    pub is_pressed: bool,
+}
+
+/// Component that focuses light into a ray along the x axis of its pose,
+/// heating up whatever the ray hits first.
+#[derive(Clone, Copy, Debug)]
+pub struct Lens {
+    /// Temperature added per second to the thing hit.
+    pub heat: f64,
+    pub range: f64,
+    /// Thickness of the ray.
+    pub radius: f64,
+}
+
+/// Small flame-colored circle for torches and igniters, dimmer when not lit.
+pub fn heat_source_mesh(is_lit: bool) -> sf::Mesh {
+    let color = if is_lit {
+        [1.0, 0.6, 0.1, 1.0]
+    } else {
+        [0.4, 0.2, 0.1, 1.0]
+    };
+    let shape = sf::ConvexMeshShape::Circle {
+        r: HEAT_SOURCE_RADIUS,
+        points: 12,
+    };
+    sf::Mesh::from(shape).with_color(color)
+}
+
+/// Collect heat from every heat source into `delta_temps`.
+pub(super) fn collect_heat(
+    dt: f64,
+    physics: &sf::PhysicsWorld,
+    world: &mut sf::hecs::World,
+    hecs_sync: &sf::HecsSyncManager,
+    delta_temps: &mut Vec<(sf::hecs::Entity, f64)>,
+) {
+    for (_, (source, igniter, mesh)) in
+        world.query_mut::<(&mut HeatSource, &mut TimedIgniter, &mut sf::Mesh)>()
+    {
+        igniter.elapsed += dt;
+        let burn_end = igniter.delay + igniter.duration.unwrap_or(f64::INFINITY);
+        let was_active = source.is_active;
+        source.is_active = igniter.elapsed >= igniter.delay && igniter.elapsed < burn_end;
+        if source.is_active != was_active {
+            *mesh = heat_source_mesh(source.is_active);
+        }
+    }
+
+    for (_, (source, pose)) in world.query::<(&HeatSource, &sf::Pose)>().iter() {
+        if !source.is_active {
+            continue;
+        }
+        let area = sf::Collider::new_circle(source.radius).shape;
+        for (coll_key, _) in physics.query_shape(*pose, area, Default::default()) {
+            let Some(entity) = hecs_sync.get_collider_entity(coll_key) else { continue };
+            delta_temps.push((entity, source.heat * dt));
+        }
+    }
+
+    for (_, (lens, pose)) in world.query::<(&Lens, &sf::Pose)>().iter() {
+        let ray = sf::Ray {
+            start: pose.translation,
+            dir: sf::Unit::new_normalize(pose.rotation * sf::Vec2::unit_x()),
+        };
+        let Some(hit) = physics.spherecast(lens.radius, ray, lens.range) else { continue };
+        let Some(entity) = hecs_sync.get_collider_entity(hit.collider) else { continue };
+        delta_temps.push((entity, lens.heat * dt));
+    }
+
+    // switches ignite their target instantly, once per press.
+    // only things with a body count, static geometry touching the plate doesn't
+    let mut triggered = Vec::new();
+    for (_, (switch, &coll_key)) in world.query_mut::<(&mut PressureSwitch, &sf::ColliderKey)>() {
+        let was_pressed = switch.is_pressed;
+        switch.is_pressed = physics.contacts_for_collider(coll_key).any(|contact| {
+            let [a, b] = contact.colliders;
+            let other = if a == coll_key { b } else { a };
+            physics.entity_set.get_collider_body_key(other).is_some()
+        });
+        if switch.is_pressed && !was_pressed {
+            triggered.push(switch.target);
+        }
+    }
+    if triggered.is_empty() {
+        return;
+    }
+    for (entity, (id, flammable)) in world.query_mut::<(&ObjectId, &Flammable)>() {
+        if triggered.contains(&id.0) {
+            delta_temps.push((entity, flammable.ignition_temperature()));
+        }
+    }
+}
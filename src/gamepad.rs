@@ -0,0 +1,111 @@
+//! Gamepad input, which starframe doesn't handle so we read it directly with gilrs.
+
+use std::collections::HashMap;
+
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs};
+use starframe as sf;
+
+/// State of the most recently used gamepad.
+pub struct GamepadInput {
+    // None if gamepads aren't supported on this system
+    gilrs: Option<Gilrs>,
+    active: Option<GamepadId>,
+    // buttons pressed and released during the last tick
+    pressed: Vec<Button>,
+    released: Vec<Button>,
+    // number of ticks each button has been held for
+    held: HashMap<Button, usize>,
+}
+
+impl GamepadInput {
+    pub fn new() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(err) => {
+                eprintln!("gamepad support unavailable: {err}");
+                None
+            }
+        };
+        let active = gilrs
+            .as_ref()
+            .and_then(|gilrs| gilrs.gamepads().next())
+            .map(|(id, _)| id);
+        Self {
+            gilrs,
+            active,
+            pressed: Vec::new(),
+            released: Vec::new(),
+            held: HashMap::new(),
+        }
+    }
+
+    /// Process events that came in since the last call.
+    /// Must be called once per tick before reading the state.
+    pub fn update(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        let Some(gilrs) = &mut self.gilrs else { return };
+
+        while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+            // whichever gamepad was touched last is the one we listen to
+            if matches!(
+                event,
+                EventType::ButtonPressed(..) | EventType::AxisChanged(..)
+            ) && self.active != Some(id)
+            {
+                self.active = Some(id);
+                self.held.clear();
+            }
+            if self.active != Some(id) {
+                continue;
+            }
+            match event {
+                EventType::ButtonPressed(button, _) => self.pressed.push(button),
+                EventType::ButtonReleased(button, _) => self.released.push(button),
+                EventType::Disconnected => {
+                    self.active = None;
+                    self.held.clear();
+                }
+                _ => {}
+            }
+        }
+
+        let Some(gamepad) = self.active.map(|id| gilrs.gamepad(id)) else { return };
+        self.held.retain(|&button, _| gamepad.is_pressed(button));
+        for &button in &self.pressed {
+            self.held.entry(button).or_insert(0);
+        }
+        for ticks in self.held.values_mut() {
+            *ticks += 1;
+        }
+    }
+
+    /// Whether the button was pressed down this tick.
+    pub fn pressed(&self, button: Button) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    /// Whether the button was released this tick.
+    pub fn released(&self, button: Button) -> bool {
+        self.released.contains(&button)
+    }
+
+    /// Whether the button has been held for exactly the given number of ticks,
+    /// counting the tick it was pressed on as the first.
+    pub fn held_exact(&self, button: Button, ticks: usize) -> bool {
+        self.held.get(&button) == Some(&ticks)
+    }
+
+    /// Position of a stick with a radial deadzone applied,
+    /// rescaled so that the edge of the deadzone is zero and full tilt is length one.
+    pub fn stick(&self, x_axis: Axis, y_axis: Axis, deadzone: f64) -> sf::Vec2 {
+        let Some(gamepad) = self.active.and_then(|id| self.gilrs.as_ref()?.connected_gamepad(id)) else { return sf::Vec2::zero() };
+        let raw = sf::Vec2::new(gamepad.value(x_axis) as f64, gamepad.value(y_axis) as f64);
+        let mag = raw.mag();
+        if mag <= deadzone {
+            return sf::Vec2::zero();
+        }
+        let scaled_mag = ((mag - deadzone) / (1.0 - deadzone)).min(1.0);
+        raw * (scaled_mag / mag)
+    }
+}
@@ -0,0 +1,169 @@
+//! Win conditions for levels.
+//!
+//! Every level has a goal, set with the map properties `goal` and `time_limit` in Tiled.
+//! By default the goal is to reach a `LevelExit`.
+
+use starframe as sf;
+
+/// Marker component for things that have to be burned for a `BurnAllTargets` goal.
+/// Every `Weed` is one, other objects can be marked with the `burn_target` property.
+#[derive(Clone, Copy, Debug)]
+pub struct BurnTarget;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub enum GoalKind {
+    #[default]
+    ReachExit,
+    /// Burn every entity with a `BurnTarget`.
+    BurnAllTargets,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LevelGoal {
+    pub kind: GoalKind,
+    /// Seconds to complete the goal in before the level restarts.
+    pub time_limit: Option<f64>,
+}
+
+/// Something that happened with the level's goal this tick.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GoalEvent {
+    Completed,
+    /// Ran out of time.
+    Failed,
+}
+
+/// Tracks progress towards the current level's goal.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GoalTracker {
+    goal: LevelGoal,
+    time: f64,
+    is_finished: bool,
+}
+
+impl GoalTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a goal from the beginning.
+    pub fn start(&mut self, goal: LevelGoal) {
+        *self = Self {
+            goal,
+            ..Self::default()
+        };
+    }
+
+    /// Seconds left to complete the goal, if it has a time limit.
+    pub fn time_left(&self) -> Option<f64> {
+        self.goal
+            .time_limit
+            .map(|limit| (limit - self.time).max(0.0))
+    }
+
+    /// Check if the goal has been completed or failed.
+    /// Only reports each once, call `start` to track again.
+    pub fn tick(
+        &mut self,
+        dt: f64,
+        world: &mut sf::hecs::World,
+        reached_exit: bool,
+    ) -> Option<GoalEvent> {
+        if self.is_finished {
+            return None;
+        }
+        self.time += dt;
+
+        let is_complete = match self.goal.kind {
+            GoalKind::ReachExit => reached_exit,
+            // targets are despawned when they burn away
+            GoalKind::BurnAllTargets => world
+                .query_mut::<&BurnTarget>()
+                .into_iter()
+                .next()
+                .is_none(),
+        };
+        let event = if is_complete {
+            Some(GoalEvent::Completed)
+        } else if self.time_left() == Some(0.0) {
+            Some(GoalEvent::Failed)
+        } else {
+            None
+        };
+        self.is_finished = event.is_some();
+        event
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f64 = 0.1;
+
+    fn tracker(kind: GoalKind, time_limit: Option<f64>) -> GoalTracker {
+        let mut tracker = GoalTracker::new();
+        tracker.start(LevelGoal { kind, time_limit });
+        tracker
+    }
+
+    #[test]
+    fn reaching_the_exit_completes_once() {
+        let mut world = sf::hecs::World::new();
+        let mut goals = tracker(GoalKind::ReachExit, None);
+        assert_eq!(goals.tick(DT, &mut world, false), None);
+        assert_eq!(goals.tick(DT, &mut world, true), Some(GoalEvent::Completed));
+        assert_eq!(goals.tick(DT, &mut world, true), None);
+
+        goals.start(LevelGoal::default());
+        assert_eq!(goals.tick(DT, &mut world, true), Some(GoalEvent::Completed));
+    }
+
+    #[test]
+    fn burning_every_target_completes() {
+        let mut world = sf::hecs::World::new();
+        let first = world.spawn((BurnTarget,));
+        let second = world.spawn((BurnTarget,));
+        let mut goals = tracker(GoalKind::BurnAllTargets, None);
+        assert_eq!(goals.tick(DT, &mut world, false), None);
+
+        world.despawn(first).unwrap();
+        // reaching the exit doesn't count for this goal
+        assert_eq!(goals.tick(DT, &mut world, true), None);
+
+        world.despawn(second).unwrap();
+        assert_eq!(
+            goals.tick(DT, &mut world, false),
+            Some(GoalEvent::Completed)
+        );
+    }
+
+    #[test]
+    fn running_out_of_time_fails_once() {
+        let mut world = sf::hecs::World::new();
+        let mut goals = tracker(GoalKind::ReachExit, Some(0.25));
+        assert_eq!(goals.tick(DT, &mut world, false), None);
+        assert_eq!(goals.tick(DT, &mut world, false), None);
+        assert!((goals.time_left().unwrap() - 0.05).abs() < 1e-9);
+        assert_eq!(goals.tick(DT, &mut world, false), Some(GoalEvent::Failed));
+        assert_eq!(goals.time_left(), Some(0.0));
+        assert_eq!(goals.tick(DT, &mut world, true), None);
+    }
+
+    #[test]
+    fn completing_on_the_last_tick_wins_over_failing() {
+        let mut world = sf::hecs::World::new();
+        let mut goals = tracker(GoalKind::ReachExit, Some(DT));
+        assert_eq!(goals.tick(DT, &mut world, true), Some(GoalEvent::Completed));
+    }
+
+    #[test]
+    fn no_time_limit_never_fails() {
+        let mut world = sf::hecs::World::new();
+        let mut goals = tracker(GoalKind::ReachExit, None);
+        assert_eq!(goals.time_left(), None);
+        for _ in 0..1000 {
+            assert_eq!(goals.tick(DT, &mut world, false), None);
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! The sequence of levels in the game.
+
+use assets_manager::{loader, Asset};
+
+/// List of scenes in the order they're played.
+/// Finishing the last one goes back to the first.
+#[derive(Clone, Debug, serde::Deserialize)]
+pub struct LevelList {
+    /// Asset ids of the scenes, e.g. `scenes.test`.
+    pub levels: Vec<String>,
+}
+impl Asset for LevelList {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+/// Marker component for an area that takes the player to the next level when entered.
+#[derive(Clone, Copy, Debug)]
+pub struct LevelExit;
@@ -2,12 +2,25 @@ use assets_manager::{AssetCache, Handle};
 use lazy_static::lazy_static;
 use starframe as sf;
 
+mod bench;
+mod embers;
+use embers::Embers;
 mod fire;
+use fire::FireSystem;
+mod gamepad;
+use gamepad::GamepadInput;
+mod goals;
+use goals::{GoalEvent, GoalTracker};
+mod levels;
+use levels::{LevelExit, LevelList};
 mod player;
+mod rebind;
+mod save;
+use save::{SaveData, SavedCheckpoint};
 mod scene;
 use scene::Scene;
 mod settings;
-use settings::Settings;
+use settings::{Action, Keymap, Settings, SettingsOverrides};
 
 //
 // Constants & init
@@ -20,12 +33,24 @@ mod collision_layers {
     pub const PLAYER: usize = 1;
     /// Things that are only interacted with by the player
     pub const INTERACTABLE: usize = 2;
+    /// Embers thrown off by fire, which land on things but don't hit the player or each other
+    pub const EMBER: usize = 3;
+    /// Pressure switches, which only detect physics objects on the default layer and torches
+    pub const PRESSURE_SWITCH: usize = 4;
+    /// Torches, which rest on things and press switches but don't get in the player's way
+    pub const TORCH: usize = 5;
 
     pub(super) fn create_layer_matrix() -> sf::CollisionMaskMatrix {
         let mut mat = sf::CollisionMaskMatrix::default();
         mat.ignore(PLAYER, ROPE_LAYER);
         mat.ignore_all(INTERACTABLE);
         mat.unignore(INTERACTABLE, PLAYER);
+        mat.ignore(EMBER, PLAYER);
+        mat.ignore(EMBER, EMBER);
+        mat.ignore_all(PRESSURE_SWITCH);
+        mat.unignore(PRESSURE_SWITCH, 0);
+        mat.unignore(PRESSURE_SWITCH, TORCH);
+        mat.ignore(TORCH, PLAYER);
         mat
     }
 }
@@ -36,6 +61,14 @@ lazy_static! {
 pub type AssetHandle<T> = Handle<'static, T>;
 
 fn main() {
+    // command line tools that don't start the game
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("validate-scene") => std::process::exit(validate_scenes(&args[1..])),
+        Some("bench-fire") => std::process::exit(bench::bench_fire(&args[1..])),
+        _ => {}
+    }
+
     #[cfg(debug_assertions)]
     ASSETS.enhance_hot_reloading();
 
@@ -52,37 +85,127 @@ fn main() {
     });
 }
 
+/// Check the given Tiled map files for problems and print them.
+/// Returns the process exit code, nonzero if any scene failed to load or has errors.
+fn validate_scenes(paths: &[String]) -> i32 {
+    let mut any_failed = false;
+    for path in paths {
+        println!("{path}:");
+        let issues = match std::fs::read(path) {
+            Ok(content) => match scene::parse_scene(&content) {
+                Ok(scene) => {
+                    let issues = scene.validate();
+                    any_failed |= issues.iter().any(|issue| issue.is_error());
+                    issues
+                }
+                Err(err) => {
+                    any_failed = true;
+                    err.issues
+                }
+            },
+            Err(err) => {
+                any_failed = true;
+                println!("  error: {err}");
+                continue;
+            }
+        };
+        if issues.is_empty() {
+            println!("  ok");
+        }
+        for issue in issues {
+            println!("  {issue}");
+        }
+    }
+
+    if any_failed {
+        1
+    } else {
+        0
+    }
+}
+
+/// Print an error along with the chain of errors that caused it.
+fn print_error(err: &dyn std::error::Error) {
+    eprintln!("{err}");
+    let mut source = err.source();
+    while let Some(err) = source {
+        eprintln!("  caused by: {err}");
+        source = err.source();
+    }
+}
+
 //
 // State types
 //
 
+/// Time between the player dying and the level restarting.
+const RESPAWN_DELAY: f64 = 1.0;
+/// Time the outcome of a level's goal is shown before moving on.
+const GOAL_OUTCOME_DELAY: f64 = 1.5;
+
 enum StateEnum {
     Playing,
     Paused,
+    /// Going through `Action::ALL` asking for a new binding for each.
+    Rebinding {
+        action_idx: usize,
+    },
 }
 pub struct State {
     // systems
     world: sf::hecs::World,
     physics: sf::PhysicsWorld,
     hecs_sync: sf::HecsSyncManager,
+    fire: FireSystem,
+    embers: Embers,
+    goals: GoalTracker,
     camera: sf::Camera,
     camera_ctl: sf::MouseDragCameraController,
+    gamepad: GamepadInput,
     mesh_renderer: sf::MeshRenderer,
     debug_visualizer: sf::DebugVisualizer,
     grid_vis_active: bool,
     // content
     settings: AssetHandle<Settings>,
+    user_settings: SettingsOverrides,
+    save: SaveData,
     state: StateEnum,
+    levels: AssetHandle<LevelList>,
+    level_idx: usize,
     scene: AssetHandle<Scene>,
+    kill_plane_y: f64,
     player: player::PlayerController,
+    // counts down after the player dies
+    respawn_timer: Option<f64>,
+    // how the level's goal ended, shown until the timer runs out and the level changes
+    goal_outcome: Option<(GoalEvent, f64)>,
 }
 impl State {
     fn init(renderer: &sf::Renderer) -> Self {
-        let scene = ASSETS
-            .load::<Scene>("scenes.test")
-            .expect("test scene failed to load");
-
-        State {
+        let save = SaveData::load();
+        let levels: AssetHandle<LevelList> =
+            ASSETS.load("levels").expect("level list failed to load");
+
+        // continue from the last checkpoint if there is one
+        let (level_idx, checkpoint) = save
+            .checkpoint
+            .as_ref()
+            .and_then(|saved| {
+                let level_idx = levels
+                    .read()
+                    .levels
+                    .iter()
+                    .position(|l| *l == saved.level)?;
+                Some((level_idx, saved.spawn_point))
+            })
+            .unwrap_or((0, None));
+        let level_id = levels.read().levels.get(level_idx).cloned();
+        let Some(scene) = level_id.as_deref().and_then(load_scene) else {
+            eprintln!("could not load the starting level");
+            std::process::exit(1);
+        };
+
+        let mut state = State {
             world: sf::hecs::World::new(),
             physics: sf::PhysicsWorld::new(
                 sf::physics::TuningConstants {
@@ -91,6 +214,9 @@ impl State {
                 collision_layers::create_layer_matrix(),
             ),
             hecs_sync: sf::HecsSyncManager::new_autosync(sf::HecsSyncOptions::both_ways()),
+            fire: FireSystem::new(),
+            embers: Embers::new(),
+            goals: GoalTracker::new(),
             camera: sf::Camera::new(sf::CameraScalingStrategy::ConstantDisplayArea {
                 width: 30.0,
                 height: 15.0,
@@ -100,27 +226,169 @@ impl State {
                 reset_button: None,
                 ..Default::default()
             },
+            gamepad: GamepadInput::new(),
             mesh_renderer: sf::MeshRenderer::new(renderer),
             debug_visualizer: sf::DebugVisualizer::new(renderer),
             grid_vis_active: false,
             //
             settings: ASSETS.load("settings").expect("settings failed to load"),
+            user_settings: save::load_settings_overrides(),
+            save,
             state: StateEnum::Playing,
+            levels,
+            level_idx,
             scene,
+            kill_plane_y: 0.0,
             player: player::PlayerController::new(),
-        }
+            respawn_timer: None,
+            goal_outcome: None,
+        };
+        state.player.set_checkpoint(checkpoint);
+        state.unlock_current_level();
+        state.restart_level();
+        state
     }
 
     fn reset(&mut self) {
+        self.player.forget_world();
         self.physics.clear();
         self.world.clear();
+        self.fire.reset();
         self.camera.transform = sf::Transform::identity();
     }
 
     fn instantiate_scene(&mut self) {
-        self.scene
-            .read()
-            .instantiate(&mut self.camera, &mut self.physics, &mut self.world);
+        let scene = self.scene.read();
+        scene.instantiate(&mut self.camera, &mut self.physics, &mut self.world);
+        self.goals.start(scene.goal());
+        self.player.reset_vines(scene.vine_budget());
+    }
+
+    /// Reset the world to the initial state of the current scene and respawn the player.
+    fn restart_level(&mut self) {
+        self.reset();
+        self.instantiate_scene();
+        self.kill_plane_y = self.scene.read().kill_plane_y();
+        self.respawn_timer = None;
+        self.goal_outcome = None;
+        self.player.respawn(&mut self.physics, &mut self.world);
+    }
+
+    /// Move on to the next level in the level list,
+    /// or back to the first one after the last.
+    /// Stays on the current level if the next one fails to load.
+    fn advance_level(&mut self) {
+        let levels = self.levels.read();
+        if levels.levels.is_empty() {
+            return;
+        }
+        let next_idx = if self.level_idx + 1 < levels.levels.len() {
+            self.level_idx + 1
+        } else {
+            // there's no ending yet, so finishing the last level starts the game over
+            0
+        };
+        let Some(scene) = load_scene(&levels.levels[next_idx]) else { return };
+        drop(levels);
+
+        self.level_idx = next_idx;
+        self.scene = scene;
+        self.player.set_checkpoint(None);
+        self.unlock_current_level();
+        self.save_checkpoint();
+        self.restart_level();
+    }
+
+    fn current_level_id(&self) -> Option<String> {
+        self.levels.read().levels.get(self.level_idx).cloned()
+    }
+
+    fn unlock_current_level(&mut self) {
+        let Some(level) = self.current_level_id() else { return };
+        if self.save.unlocked_levels.insert(level) {
+            self.save.store();
+        }
+    }
+
+    /// Store the player's current checkpoint so the game continues from it next time.
+    fn save_checkpoint(&mut self) {
+        let Some(level) = self.current_level_id() else { return };
+        self.save.checkpoint = Some(SavedCheckpoint {
+            level,
+            spawn_point: self.player.checkpoint(),
+        });
+        self.save.store();
+    }
+
+    /// Capture a new binding for one action in rebinding mode
+    /// and move on to the next action once we have one.
+    /// The exit key skips an action, keeping its current binding.
+    fn rebind_step(&mut self, input: &sf::Input, keys: &Keymap, action_idx: usize) {
+        let action = Action::ALL[action_idx];
+        if !input.button(keys.menus.exit.into()) {
+            let Some(binding) = rebind::capture_binding(input, action) else { return };
+            // binding back to the default removes the override
+            // so that future changes to the defaults apply again
+            let result = if self.settings.read().keymap.binding(action) == binding {
+                self.user_settings.reset_binding(action);
+                Ok(())
+            } else {
+                self.user_settings.rebind(action, binding)
+            };
+            if let Err(err) = result {
+                eprintln!("failed to rebind {action}: {err}");
+                return;
+            }
+
+            let keys = self.user_settings.apply(&self.settings.read()).keymap;
+            for other in keys.conflicts(action) {
+                println!("  warning: {binding} is also bound to {other}");
+            }
+        }
+
+        match Action::ALL.get(action_idx + 1) {
+            Some(&next) => {
+                let keys = self.user_settings.apply(&self.settings.read()).keymap;
+                print_rebind_prompt(next, &keys);
+                self.state = StateEnum::Rebinding {
+                    action_idx: action_idx + 1,
+                };
+            }
+            None => {
+                save::store_settings_overrides(&self.user_settings);
+                println!("controls saved");
+                self.state = StateEnum::Playing;
+            }
+        }
+    }
+}
+
+fn print_rebind_prompt(action: Action, keys: &Keymap) {
+    let kind = if action.uses_mouse() {
+        "mouse button"
+    } else {
+        "key"
+    };
+    println!(
+        "press a {kind} for {action} (currently {}), or {:?} to keep it",
+        keys.binding(action),
+        keys.menus.exit
+    );
+}
+
+/// Load a scene and print any problems found in it.
+fn load_scene(id: &str) -> Option<AssetHandle<Scene>> {
+    match ASSETS.load::<Scene>(id) {
+        Ok(scene) => {
+            for issue in scene.read().validate() {
+                eprintln!("{id}: {issue}");
+            }
+            Some(scene)
+        }
+        Err(err) => {
+            print_error(&err);
+            None
+        }
     }
 }
 
@@ -134,8 +402,15 @@ impl sf::GameState for State {
     }
 
     fn tick(&mut self, game: &sf::Game) -> Option<()> {
-        let settings = self.settings.read();
+        let settings = self.user_settings.apply(&self.settings.read());
         let keys = settings.keymap;
+        self.gamepad.update();
+
+        // rebinding captures every key, including exit
+        if let StateEnum::Rebinding { action_idx } = self.state {
+            self.rebind_step(&game.input, &keys, action_idx);
+            return Some(());
+        }
 
         // while we don't have a real menu, just exit the game on keypress
         if game.input.button(keys.menus.exit.into()) {
@@ -147,8 +422,7 @@ impl sf::GameState for State {
 
         // reload scene
         if game.input.button(keys.menus.reload.into()) {
-            self.reset();
-            self.instantiate_scene();
+            self.restart_level();
         }
 
         // toggle debug visualization
@@ -156,6 +430,13 @@ impl sf::GameState for State {
             self.grid_vis_active = !self.grid_vis_active;
         }
 
+        if game.input.button(keys.debug.rebind.into()) {
+            println!("rebinding controls");
+            print_rebind_prompt(Action::ALL[0], &keys);
+            self.state = StateEnum::Rebinding { action_idx: 0 };
+            return Some(());
+        }
+
         self.camera_ctl.update(&mut self.camera, &game.input);
 
         match self.state {
@@ -175,25 +456,85 @@ impl sf::GameState for State {
 
                 let grav = sf::forcefield::Gravity(sf::Vec2::new(0.0, -9.81));
                 self.physics
-                    .tick(game.dt_fixed, self.player.time_scale(), &grav);
-
-                self.player.tick(
-                    &game.input,
-                    &mut self.camera,
-                    &keys.player,
-                    &mut self.physics,
-                    &mut self.world,
-                );
+                    .tick(game.dt_fixed, self.player.time_scale(&keys.player), &grav);
+
+                self.player.tick(player::TickContext {
+                    dt: game.dt_fixed,
+                    input: &game.input,
+                    gamepad: &self.gamepad,
+                    camera: &mut self.camera,
+                    keys: &keys.player,
+                    pad_keys: &keys.gamepad,
+                    physics: &mut self.physics,
+                    hecs_sync: &self.hecs_sync,
+                    world: &mut self.world,
+                });
 
                 self.hecs_sync
                     .sync_physics_to_hecs(&self.physics, &mut self.world);
 
-                fire::tick(
+                // die and restart from the last checkpoint
+                if let Some(timer) = &mut self.respawn_timer {
+                    *timer -= game.dt_fixed;
+                    if *timer <= 0.0 {
+                        self.restart_level();
+                        return Some(());
+                    }
+                } else if self.goal_outcome.is_none()
+                    && self.player.check_death(
+                        &self.physics,
+                        &self.hecs_sync,
+                        &self.world,
+                        self.kill_plane_y,
+                    )
+                {
+                    self.player.kill(&mut self.world);
+                    self.respawn_timer = Some(RESPAWN_DELAY);
+                }
+
+                if self
+                    .player
+                    .update_checkpoint(&self.physics, &self.hecs_sync, &self.world)
+                {
+                    self.save_checkpoint();
+                }
+                let reached_exit = self
+                    .player
+                    .touching::<LevelExit>(&self.physics, &self.hecs_sync, &self.world)
+                    .is_some();
+
+                self.fire.tick(
                     game.dt_fixed,
                     &mut self.physics,
                     &mut self.world,
                     &mut self.hecs_sync,
                 );
+                self.embers.tick(
+                    game.dt_fixed,
+                    &mut self.fire,
+                    &mut self.physics,
+                    &mut self.world,
+                    &self.hecs_sync,
+                );
+                self.fire.update_visuals(&mut self.world);
+
+                // leave the level a moment after the goal ends
+                if let Some((event, timer)) = &mut self.goal_outcome {
+                    *timer -= game.dt_fixed;
+                    if *timer <= 0.0 {
+                        let event = *event;
+                        self.goal_outcome = None;
+                        match event {
+                            GoalEvent::Completed => self.advance_level(),
+                            GoalEvent::Failed => self.restart_level(),
+                        }
+                    }
+                } else if let Some(event) =
+                    self.goals
+                        .tick(game.dt_fixed, &mut self.world, reached_exit)
+                {
+                    self.goal_outcome = Some((event, GOAL_OUTCOME_DELAY));
+                }
 
                 Some(())
             }
@@ -205,20 +546,23 @@ impl sf::GameState for State {
 
                 Some(())
             }
+            StateEnum::Rebinding { .. } => unreachable!("handled above"),
         }
     }
 
     fn draw(&mut self, renderer: &mut sf::Renderer, dt: f32) {
         let mut ctx = renderer.draw_to_window();
-        ctx.clear(sf::wgpu::Color {
-            r: 0.00802,
-            g: 0.0137,
-            b: 0.02732,
-            a: 1.0,
-        });
-
+        // tint the background while showing how the level's goal ended
+        let (r, g, b) = match self.goal_outcome {
+            None => (0.00802, 0.0137, 0.02732),
+            Some((GoalEvent::Completed, _)) => (0.0137, 0.04, 0.02),
+            Some((GoalEvent::Failed, _)) => (0.05, 0.0102, 0.0102),
+        };
+        ctx.clear(sf::wgpu::Color { r, g, b, a: 1.0 });
+
+        let keys = self.user_settings.apply(&self.settings.read()).keymap;
         sf::animator::step_time(
-            dt * self.player.time_scale().unwrap_or(1.0) as f32,
+            dt * self.player.time_scale(&keys.player).unwrap_or(1.0) as f32,
             &mut self.world,
         );
         sf::animator::update_joints(&mut self.world);
@@ -1,41 +1,151 @@
 //! Player controller
 
-use assets_manager::asset::Gltf;
+use assets_manager::{asset::Gltf, loader, Asset};
 
 use starframe as sf;
 
-use crate::{fire::Flammable, AssetHandle, ASSETS};
-
-// tuning constants
-
-const COLL_R: f64 = 0.3;
-const COLL_LENGTH: f64 = 1.2;
-const PLAYER_MASS: f64 = 1.0;
-const GROUNDED_ANGLE_LIMIT: f64 = 60.0;
-const BASE_MOVE_SPEED: f64 = 6.0;
-const GROUND_ACCEL: f64 = 1.0;
-const AIR_ACCEL: f64 = 0.3;
-const ROPE_SWINGING_ACCEL: f64 = 0.1;
-const JUMP_VEL: f64 = 8.0;
-const ROPE_START_OFFSET: f64 = 0.25;
-const ROPE_MAX_LENGTH: f64 = 8.0;
-const SPHERECAST_RADIUS: f64 = 0.1;
-const ROPE_MIN_LENGTH: f64 = 1.0;
-const BOOST_ANGLE_LIMIT: f64 = 60.0;
-const BOOST_BONUS_SPEED: f64 = 0.1;
-const AIM_TIME_SCALE: f64 = 0.1;
-
-/// Marker component indicating a player spawn point, must be attached to a Pose.
+use crate::{
+    fire::{self, BurnVisuals, FireMaterials, Flammable, Torch},
+    gamepad::GamepadInput,
+    settings::{AimMode, GamepadKeys, PlayerKeys},
+    AssetHandle, ASSETS,
+};
+
+/// Numbers that define how the player moves, loaded from `assets/player_tuning.json`.
 ///
-/// For now, we just find the first one and spawn the player on it.
-/// Eventually these will work as checkpoints.
+/// Hot reloaded in debug builds. Collider size and mass only update on respawn.
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+pub struct PlayerTuning {
+    pub coll_r: f64,
+    pub coll_length: f64,
+    pub mass: f64,
+    /// Steepest slope (in degrees) that counts as ground.
+    pub grounded_angle_limit: f64,
+    pub base_move_speed: f64,
+    pub ground_accel: f64,
+    pub air_accel: f64,
+    pub rope_swinging_accel: f64,
+    pub jump_vel: f64,
+    /// Distance from the player where new vines start.
+    pub rope_start_offset: f64,
+    pub rope_max_length: f64,
+    pub rope_min_length: f64,
+    /// Thickness of the ray used to find aim targets.
+    pub spherecast_radius: f64,
+    /// Largest angle (in degrees) between velocity and the swing direction
+    /// where attaching a vine gives a speed boost.
+    pub boost_angle_limit: f64,
+    pub boost_bonus_speed: f64,
+    /// How far off from the aim stick direction to look for something to attach to, in degrees.
+    pub aim_assist_angle: f64,
+    /// How many directions to try on each side of the aim direction, 0 to turn aim assist off.
+    pub aim_assist_steps: usize,
+    /// How close to a torch the player needs to be to pick it up.
+    pub torch_pickup_range: f64,
+    /// How fast a retracting vine pulls the player in, in units per second.
+    pub vine_retract_speed: f64,
+    /// How fast the player climbs up and down a vine.
+    pub vine_climb_speed: f64,
+}
+
+impl Asset for PlayerTuning {
+    const EXTENSION: &'static str = "json";
+
+    type Loader = loader::JsonLoader;
+}
+
+/// Component indicating a player spawn point, must be attached to a Pose.
+///
+/// Spawn points work as checkpoints: touching one activates it,
+/// and the player respawns at the most recently activated one.
+/// Before any are activated, the one with the lowest `order` is used.
 #[derive(Clone, Copy, Debug)]
-pub struct PlayerSpawnPoint;
+pub struct PlayerSpawnPoint {
+    /// Tiled object id, stable across scene resets unlike the entity.
+    pub id: u32,
+    pub order: i32,
+}
+
+/// Marker component for things that kill the player on touch.
+#[derive(Clone, Copy, Debug)]
+pub struct Hazard;
+
+/// Everything `PlayerController::tick` needs from the rest of the game.
+pub struct TickContext<'a> {
+    pub dt: f64,
+    pub input: &'a sf::Input,
+    pub gamepad: &'a GamepadInput,
+    pub camera: &'a mut sf::Camera,
+    pub keys: &'a PlayerKeys,
+    pub pad_keys: &'a GamepadKeys,
+    pub physics: &'a mut sf::PhysicsWorld,
+    pub hecs_sync: &'a sf::HecsSyncManager,
+    pub world: &'a mut sf::hecs::World,
+}
+
+/// Limits on the vines the player can have at once, set per level.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VineBudget {
+    pub max_vines: Option<usize>,
+    /// Maximum length of all vines combined.
+    pub max_total_length: Option<f64>,
+}
+
+impl VineBudget {
+    /// Whether `new_vine_count` more vines with a combined length of `new_length` fit
+    /// on top of `vine_count` existing ones that are `used_length` long together.
+    fn allows(
+        &self,
+        vine_count: usize,
+        used_length: f64,
+        new_vine_count: usize,
+        new_length: f64,
+    ) -> bool {
+        let count_fits = self
+            .max_vines
+            .map_or(true, |max| vine_count + new_vine_count <= max);
+        count_fits && new_length <= self.remaining_length(used_length)
+    }
+
+    /// How much more vine fits when existing ones are `used_length` long together.
+    fn remaining_length(&self, used_length: f64) -> f64 {
+        let Some(max) = self.max_total_length else { return f64::INFINITY };
+        (max - used_length).max(0.0)
+    }
+}
+
+/// A vine created by the player that still exists in the world.
+#[derive(Clone, Copy, Debug)]
+struct Vine {
+    rope_key: sf::RopeKey,
+    /// What each end of the vine is attached to,
+    /// None for the end held by the player.
+    anchors: [Option<sf::ColliderKey>; 2],
+}
+
+/// Length resolution of the tether preview mesh,
+/// which is only rebuilt when the length changes by this much.
+const TETHER_PREVIEW_STEP: f64 = 0.1;
+
+/// First end of a tether picked by the player, waiting for the second one.
+#[derive(Clone, Copy, Debug)]
+struct TetherStart {
+    point: sf::Vec2,
+    collider: sf::ColliderKey,
+    // entity with a mesh showing where the tether would go
+    preview: sf::hecs::Entity,
+    // (length in steps, whether the tether can be made) of the preview mesh currently shown
+    preview_shown: (u32, bool),
+}
 
 #[derive(Clone, Copy, Debug)]
 struct AttachedVine {
     rope_key: sf::RopeKey,
     player_constraint: sf::ConstraintKey,
+    /// Maximum distance between the player and the end of the vine.
+    length: f64,
+    /// Being pulled in particle by particle, reeling the player towards the anchor.
+    is_retracting: bool,
 }
 
 /// Whatever the mouse / gamepad aim stick is currently pointing at
@@ -54,61 +164,107 @@ enum AimTargetValidity {
 }
 
 /// Controller that holds most of the player's state and handles its actions.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct PlayerController {
     entity: Option<sf::hecs::Entity>,
+    // id of the spawn point to respawn at, None to start from the beginning of the level
+    active_checkpoint: Option<u32>,
     attached_vine: Option<AttachedVine>,
-    // whether or not slow-down-time-and-show-cool-reticle aiming mode is active
-    is_aim_active: bool,
+    // every vine created in the current level, oldest first
+    vines: Vec<Vine>,
+    vine_budget: VineBudget,
+    tether_start: Option<TetherStart>,
+    aim: AimState,
     // aim target is checked even if not in aim mode to draw a simplified indicator
     aim_target: AimTarget,
+    // last direction the aim stick pointed in, None when aiming with the mouse
+    stick_aim_dir: Option<sf::Unit<sf::Vec2>>,
+    held_torch: Option<sf::hecs::Entity>,
     mesh: AssetHandle<Gltf>,
+    tuning: AssetHandle<PlayerTuning>,
+    fire_materials: AssetHandle<FireMaterials>,
 }
 
 impl PlayerController {
     pub fn new() -> Self {
         Self {
             entity: None,
+            active_checkpoint: None,
             attached_vine: None,
-            is_aim_active: false,
+            vines: Vec::new(),
+            vine_budget: VineBudget::default(),
+            tether_start: None,
+            aim: AimState::default(),
             // meaningless default that will be overwritten come first tick,
             // just making validity such that it won't be drawn
             aim_target: AimTarget {
                 point: sf::Vec2::zero(),
                 validity: AimTargetValidity::TooFar,
             },
+            stick_aim_dir: None,
+            held_torch: None,
             mesh: ASSETS
                 .load::<Gltf>("models.player")
                 .expect("Missing or invalid player model"),
+            tuning: ASSETS
+                .load::<PlayerTuning>("player_tuning")
+                .expect("Missing or invalid player tuning"),
+            fire_materials: ASSETS
+                .load::<FireMaterials>("fire_materials")
+                .expect("Missing or invalid fire materials"),
         }
     }
 
-    pub fn time_scale(&self) -> Option<f64> {
-        if self.is_aim_active {
-            Some(AIM_TIME_SCALE)
+    pub fn time_scale(&self, keys: &PlayerKeys) -> Option<f64> {
+        if self.aim.is_active {
+            Some(keys.aim_time_scale)
         } else {
             None
         }
     }
 
+    /// Drop every handle into the world without despawning anything.
+    /// Call before clearing the world, since hecs reuses the ids of cleared entities.
+    pub fn forget_world(&mut self) {
+        self.entity = None;
+        self.attached_vine = None;
+        self.vines.clear();
+        self.held_torch = None;
+        self.tether_start = None;
+    }
+
     pub fn respawn(&mut self, physics: &mut sf::PhysicsWorld, world: &mut sf::hecs::World) {
         if let Some(entity) = self.entity {
             world.despawn(entity).ok();
         }
+        self.held_torch = None;
+        if let Some(start) = self.tether_start.take() {
+            world.despawn(start.preview).ok();
+        }
 
-        let spawn_point: sf::Vec2 = match world
+        let spawn_points: Vec<(sf::Vec2, PlayerSpawnPoint)> = world
             .query_mut::<(&sf::Pose, &PlayerSpawnPoint)>()
             .into_iter()
-            .next()
-        {
-            Some((_, (spawn, _))) => spawn.translation,
-            None => sf::Vec2::zero(),
-        };
+            .map(|(_, (pose, spawn))| (pose.translation, *spawn))
+            .collect();
+        // the active checkpoint may be missing if the level has changed since it was saved,
+        // start from the beginning then
+        let active_point = spawn_points
+            .iter()
+            .find(|(_, spawn)| Some(spawn.id) == self.active_checkpoint);
+        let first_point = spawn_points
+            .iter()
+            .min_by_key(|(_, spawn)| (spawn.order, spawn.id));
+        let spawn_point = active_point
+            .or(first_point)
+            .map(|(pos, _)| *pos)
+            .unwrap_or_else(sf::Vec2::zero);
 
+        let tuning = *self.tuning.read();
         let pose = sf::Pose::new(spawn_point, sf::Angle::Deg(90.0).into());
-        let body = sf::Body::new_particle(PLAYER_MASS);
+        let body = sf::Body::new_particle(tuning.mass);
         let body_key = physics.entity_set.insert_body(body);
-        let coll = sf::Collider::new_capsule(COLL_LENGTH, COLL_R)
+        let coll = sf::Collider::new_capsule(tuning.coll_length, tuning.coll_r)
             .with_material(sf::PhysicsMaterial {
                 static_friction_coef: None,
                 dynamic_friction_coef: None,
@@ -124,7 +280,7 @@ impl PlayerController {
             .map(|b| mesh_gltf.get_buffer(&b))
             .collect();
         let mesh = sf::Mesh::from_gltf(&mesh_gltf.document, &mesh_bufs).with_offset(sf::Pose::new(
-            sf::Vec2::new(-COLL_LENGTH / 2.0 - COLL_R, 0.0),
+            sf::Vec2::new(-tuning.coll_length / 2.0 - tuning.coll_r, 0.0),
             sf::Angle::Deg(-90.0).into(),
         ));
         let skin = sf::gltf_import::load_skin(&mesh_gltf.document, &mesh_bufs)
@@ -136,14 +292,127 @@ impl PlayerController {
         self.entity = Some(world.spawn((pose, body_key, coll_key, mesh, skin, anim)));
     }
 
-    pub fn tick(
+    /// Activate the checkpoint the player is currently touching, if any.
+    /// Returns true if the active checkpoint changed.
+    pub fn update_checkpoint(
         &mut self,
-        input: &sf::Input,
-        camera: &mut sf::Camera,
-        keys: &crate::settings::PlayerKeys,
-        physics: &mut sf::PhysicsWorld,
-        world: &mut sf::hecs::World,
-    ) -> Option<()> {
+        physics: &sf::PhysicsWorld,
+        hecs_sync: &sf::HecsSyncManager,
+        world: &sf::hecs::World,
+    ) -> bool {
+        let Some(spawn_entity) = self.touching::<PlayerSpawnPoint>(physics, hecs_sync, world) else { return false };
+        let Ok(spawn) = world.get::<&PlayerSpawnPoint>(spawn_entity) else { return false };
+        let changed = self.active_checkpoint != Some(spawn.id);
+        self.active_checkpoint = Some(spawn.id);
+        changed
+    }
+
+    /// Id of the spawn point the player will respawn at.
+    pub fn checkpoint(&self) -> Option<u32> {
+        self.active_checkpoint
+    }
+
+    /// Set the spawn point to respawn at,
+    /// None to start from the beginning of the level.
+    pub fn set_checkpoint(&mut self, spawn_point_id: Option<u32>) {
+        self.active_checkpoint = spawn_point_id;
+    }
+
+    /// Forget the vines created so far and set new limits for them.
+    /// Call when the level is (re)started.
+    pub fn reset_vines(&mut self, budget: VineBudget) {
+        self.vines.clear();
+        self.vine_budget = budget;
+        // preview was cleared along with the world
+        self.tether_start = None;
+    }
+
+    /// Check if the player has died by touching a hazard or fire
+    /// or by falling below the given height.
+    pub fn check_death(
+        &self,
+        physics: &sf::PhysicsWorld,
+        hecs_sync: &sf::HecsSyncManager,
+        world: &sf::hecs::World,
+        kill_plane_y: f64,
+    ) -> bool {
+        let Some(entity) = self.entity else { return false };
+        if world
+            .get::<&sf::Pose>(entity)
+            .is_ok_and(|pose| pose.translation.y < kill_plane_y)
+        {
+            return true;
+        }
+
+        self.touched_entities(physics, hecs_sync, world)
+            .into_iter()
+            .any(|other| {
+                world.get::<&Hazard>(other).is_ok()
+                    || world
+                        .get::<&Flammable>(other)
+                        .is_ok_and(|flammable| flammable.is_burning())
+            })
+    }
+
+    /// Remove the player from the world until the next respawn.
+    pub fn kill(&mut self, world: &mut sf::hecs::World) {
+        if let Some(entity) = self.entity.take() {
+            world.despawn(entity).ok();
+        }
+        // vine constraint is removed along with the player's body
+        self.attached_vine = None;
+        self.aim.is_active = false;
+        // torch stays where it was dropped
+        self.held_torch = None;
+        if let Some(start) = self.tether_start.take() {
+            world.despawn(start.preview).ok();
+        }
+    }
+
+    /// Find an entity with the component `T` that the player is currently touching.
+    /// Used to detect interactables like level exits.
+    pub fn touching<T: sf::hecs::Component>(
+        &self,
+        physics: &sf::PhysicsWorld,
+        hecs_sync: &sf::HecsSyncManager,
+        world: &sf::hecs::World,
+    ) -> Option<sf::hecs::Entity> {
+        self.touched_entities(physics, hecs_sync, world)
+            .into_iter()
+            .find(|&other| world.get::<&T>(other).is_ok())
+    }
+
+    fn touched_entities(
+        &self,
+        physics: &sf::PhysicsWorld,
+        hecs_sync: &sf::HecsSyncManager,
+        world: &sf::hecs::World,
+    ) -> Vec<sf::hecs::Entity> {
+        let Some(entity) = self.entity else { return Vec::new() };
+        let Ok(coll_key) = world.get::<&sf::ColliderKey>(entity).map(|k| *k) else { return Vec::new() };
+        physics
+            .contacts_for_collider(coll_key)
+            .filter_map(|contact| {
+                // the player can be on either side of the contact
+                let [a, b] = contact.colliders;
+                hecs_sync.get_collider_entity(if a == coll_key { b } else { a })
+            })
+            .collect()
+    }
+
+    pub fn tick(&mut self, ctx: TickContext) -> Option<()> {
+        let TickContext {
+            dt,
+            input,
+            gamepad,
+            camera,
+            keys,
+            pad_keys,
+            physics,
+            hecs_sync,
+            world,
+        } = ctx;
+        let tuning = *self.tuning.read();
         let entity = self.entity?;
         let (player_pose, &player_body_key, &player_coll_key) = world
             .query_one_mut::<(&mut sf::Pose, &sf::BodyKey, &sf::ColliderKey)>(entity)
@@ -162,6 +431,9 @@ impl PlayerController {
                 self.attached_vine = None;
             }
         }
+        // forget vines that have been removed or burned away
+        self.vines
+            .retain(|vine| physics.rope_set.get(vine.rope_key).is_some());
 
         // hacking in camera following the player like this for now,
         // TODO: make it smooth
@@ -171,7 +443,7 @@ impl PlayerController {
         // handle contacts (groundedness, interactables)
         //
 
-        let normal_y_limit = sf::Angle::Deg(GROUNDED_ANGLE_LIMIT).rad().cos();
+        let normal_y_limit = sf::Angle::Deg(tuning.grounded_angle_limit).rad().cos();
 
         let most_downright_contact = {
             let mut lowest_cont_y = f64::MAX;
@@ -202,43 +474,60 @@ impl PlayerController {
         // controls
         //
 
-        // set aim mode if holding shoot button for long enough,
-        // exit aim mode if releasing shoot or pressing cancel
-        // (and don't aim again until pressing shoot again)
-
-        // TODO: accessibility: also allow click to activate and click again to shoot
-        if input.button(sf::ButtonQuery::mouse(keys.shoot).held_exact(keys.aim_delay)) {
-            self.is_aim_active = true;
-        }
-        if input.button(keys.cancel_aim.into())
-            || input.button(sf::ButtonQuery::mouse(keys.shoot).released())
-        {
-            self.is_aim_active = false;
-        }
+        // cancel and retract share a button by default,
+        // so only retract if cancel didn't have anything to do
+        let was_aim_active = self.aim.is_active;
+        let shoot = self.aim.update(
+            ShootInput {
+                pressed: input.button(sf::ButtonQuery::mouse(keys.shoot))
+                    || gamepad.pressed(pad_keys.shoot),
+                held_for_delay: input
+                    .button(sf::ButtonQuery::mouse(keys.shoot).held_exact(keys.aim_delay))
+                    || gamepad.held_exact(pad_keys.shoot, keys.aim_delay),
+                released: input.button(sf::ButtonQuery::mouse(keys.shoot).released())
+                    || gamepad.released(pad_keys.shoot),
+                cancel: input.button(keys.cancel_aim.into())
+                    || gamepad.pressed(pad_keys.cancel_aim),
+            },
+            keys.aim_mode,
+        );
 
         //
         // move
         //
 
-        {
-            let player_body = physics.entity_set.get_body_mut(player_body_key)?;
-
-            let target_hdir = input.axis(sf::AxisQuery {
+        // keyboard gives full speed in one of eight directions,
+        // the stick anything in between if the keyboard isn't in use
+        let key_dir = sf::Vec2::new(
+            input.axis(sf::AxisQuery {
                 pos_btn: keys.right.into(),
                 neg_btn: keys.left.into(),
-            });
-            let target_vdir = input.axis(sf::AxisQuery {
+            }),
+            input.axis(sf::AxisQuery {
                 pos_btn: keys.up.into(),
                 neg_btn: keys.down.into(),
-            });
+            }),
+        );
+        let target_dir = if key_dir != sf::Vec2::zero() {
+            key_dir
+        } else {
+            gamepad.stick(pad_keys.move_x, pad_keys.move_y, pad_keys.deadzone)
+        };
+
+        {
+            let player_body = physics.entity_set.get_body_mut(player_body_key)?;
+
+            let target_hdir = target_dir.x;
             match (groundedness, self.attached_vine) {
                 // special acceleration-based controls for in air with a rope
-                // for improved swing feel and control, hopefully
+                // for improved swing feel and control, hopefully.
+                // up and down also climb the vine, see below
                 (Groundedness::Air, Some(_rope)) => {
-                    if target_hdir != 0.0 || target_vdir != 0.0 {
-                        let target_dir =
-                            sf::Unit::new_normalize(sf::Vec2::new(target_hdir, target_vdir));
-                        player_body.velocity.linear += ROPE_SWINGING_ACCEL * *target_dir;
+                    if target_dir != sf::Vec2::zero() {
+                        let magnitude = target_dir.mag().min(1.0);
+                        let target_dir = sf::Unit::new_normalize(target_dir);
+                        player_body.velocity.linear +=
+                            tuning.rope_swinging_accel * magnitude * *target_dir;
                     }
                 }
                 // normal controls for all other situations
@@ -250,13 +539,13 @@ impl PlayerController {
                         _ => sf::Vec2::unit_x(),
                     };
 
-                    let target_hvel = target_hdir * BASE_MOVE_SPEED;
+                    let target_hvel = target_hdir * tuning.base_move_speed;
                     let accel_needed = target_hvel - player_body.velocity.linear.dot(ground_dir);
                     let max_accel = match groundedness {
-                        Groundedness::EvenGround(_) => GROUND_ACCEL,
+                        Groundedness::EvenGround(_) => tuning.ground_accel,
                         // prevent movement up a steep slope
                         Groundedness::SteepSlope(normal) if normal.x * target_hdir >= 0.0 => 0.0,
-                        _ => AIR_ACCEL,
+                        _ => tuning.air_accel,
                     };
                     let accel = if accel_needed.abs() <= max_accel {
                         accel_needed
@@ -271,11 +560,12 @@ impl PlayerController {
             // jump
             //
 
-            if input.button(keys.jump.into()) {
+            if input.button(keys.jump.into()) || gamepad.pressed(pad_keys.jump) {
                 if let Groundedness::EvenGround(normal) = groundedness {
-                    player_body.velocity.linear -= JUMP_VEL * *normal;
+                    player_body.velocity.linear -= tuning.jump_vel * *normal;
                 }
-            } else if input.button(sf::ButtonQuery::from(keys.jump).released())
+            } else if (input.button(sf::ButtonQuery::from(keys.jump).released())
+                || gamepad.released(pad_keys.jump))
                 && player_body.velocity.linear.y > 0.0
             {
                 player_body.velocity.linear.y /= 2.0;
@@ -283,23 +573,48 @@ impl PlayerController {
         }
 
         //
-        // aim with mouse
+        // aim with mouse or stick
         //
 
-        let player_to_cursor = input.cursor_position_world(camera) - player_pose.translation;
-        let ray_dir = sf::Unit::new_normalize(player_to_cursor);
+        // the stick keeps aiming where it was last pointed
+        // until the mouse is used again
+        let aim_stick = gamepad.stick(pad_keys.aim_x, pad_keys.aim_y, pad_keys.deadzone);
+        if aim_stick != sf::Vec2::zero() {
+            self.stick_aim_dir = Some(sf::Unit::new_normalize(aim_stick));
+        } else if input.button(sf::ButtonQuery::mouse(keys.shoot)) {
+            self.stick_aim_dir = None;
+        }
+
+        let ray_dir = match self.stick_aim_dir {
+            Some(dir) => dir,
+            None => {
+                let player_to_cursor =
+                    input.cursor_position_world(camera) - player_pose.translation;
+                sf::Unit::new_normalize(player_to_cursor)
+            }
+        };
         let ray = sf::Ray {
             start: player_pose.translation,
             dir: ray_dir,
         };
-        match physics.spherecast(SPHERECAST_RADIUS, ray, ROPE_MAX_LENGTH) {
+        // can't create a new vine super close.
+        // if holding onto a vine, you can attach that to something
+        // even if it's right under your feet
+        let min_length = if self.attached_vine.is_none() {
+            tuning.rope_min_length
+        } else {
+            0.0
+        };
+        // sticks aren't very precise, snap to something nearby if there's nothing straight ahead
+        let ray = match self.stick_aim_dir {
+            Some(_) => aim_assist(physics, &tuning, ray, min_length).unwrap_or(ray),
+            None => ray,
+        };
+        match physics.spherecast(tuning.spherecast_radius, ray, tuning.rope_max_length) {
             Some(hit) => {
                 self.aim_target = AimTarget {
                     point: ray.point_at_t(hit.t),
-                    validity: if self.attached_vine.is_none() && hit.t < ROPE_MIN_LENGTH {
-                        // can't create a new vine super close.
-                        // if holding onto a vine, you can attach that to something
-                        // even if it's right under your feet
+                    validity: if hit.t < min_length {
                         AimTargetValidity::TooClose
                     } else {
                         AimTargetValidity::Valid {
@@ -310,7 +625,7 @@ impl PlayerController {
             }
             None => {
                 self.aim_target = AimTarget {
-                    point: ray.point_at_t(ROPE_MAX_LENGTH),
+                    point: ray.point_at_t(tuning.rope_max_length),
                     validity: AimTargetValidity::TooFar,
                 };
             }
@@ -320,15 +635,33 @@ impl PlayerController {
         // shoot vines
         //
 
+        // new vines and extending the held one both need to fit in the budget,
+        // measured along the line the new particles will be placed on
+        let (new_vine_count, new_length) = match self.attached_vine {
+            None => {
+                let rope_end = ray.point_at_t(tuning.rope_start_offset);
+                let dist = (self.aim_target.point - rope_end).mag();
+                (1, line_vine_length(dist, vine_rope_params().spacing))
+            }
+            Some(attached) => {
+                let rope = physics.rope_set.get(attached.rope_key)?;
+                let curr_end = physics
+                    .entity_set
+                    .get_body(rope.particles.last()?.body)?
+                    .pose
+                    .translation;
+                let dist = (self.aim_target.point - curr_end).mag();
+                (0, line_vine_length(dist, rope.params.spacing))
+            }
+        };
+        let shoot = shoot && self.vine_budget_allows(physics, new_vine_count, new_length);
         if let (
             true,
             AimTargetValidity::Valid {
                 collider: target_collider,
             },
-        ) = (
-            input.button(sf::ButtonQuery::mouse(keys.shoot).released()),
-            self.aim_target.validity,
-        ) {
+        ) = (shoot, self.aim_target.validity)
+        {
             match self.attached_vine {
                 //
                 // new vine
@@ -337,31 +670,29 @@ impl PlayerController {
                     let player_pos = player_pose.translation;
                     // start at the other end to control angle constraint propagation
                     let rope_start = self.aim_target.point;
-                    let rope_end = ray.point_at_t(ROPE_START_OFFSET);
+                    let rope_end = ray.point_at_t(tuning.rope_start_offset);
                     let rope = sf::Rope::spawn_line(
-                        sf::RopeParameters {
-                            bending_max_angle: sf::Angle::Deg(75.0).rad(),
-                            bending_compliance: 0.05,
-                            ..Default::default()
-                        },
+                        vine_rope_params(),
                         rope_start,
                         rope_end,
                         &mut physics.entity_set,
                     );
                     // make it flammable and add visuals to the particles
-                    for &particle in &rope.particles {
-                        let mesh = sf::Mesh::from(sf::ConvexMeshShape::Circle {
-                            r: rope.params.thickness / 2.0,
-                            points: 8,
-                        })
-                        .with_color([0.729, 0.855, 0.333, 1.0]);
-                        world.spawn((
-                            physics.entity_set.get_body(particle.body).unwrap().pose,
-                            particle.body,
-                            particle.collider,
-                            mesh,
-                            Flammable::default(),
-                        ));
+                    let particle_entities = spawn_vine_particles(
+                        rope.particles.iter().map(|particle| {
+                            let pose = physics.entity_set.get_body(particle.body).unwrap().pose;
+                            (pose, particle.body, particle.collider)
+                        }),
+                        rope.params.thickness,
+                        self.fire_materials.read().flammable("vine"),
+                        world,
+                    );
+                    // carry fire between the vine and what it's attached to
+                    if let (Some(&first), Some(target)) = (
+                        particle_entities.first(),
+                        hecs_sync.get_collider_entity(target_collider),
+                    ) {
+                        fire::link(world, first, target);
                     }
 
                     // constraint on the player
@@ -381,34 +712,23 @@ impl PlayerController {
 
                     // constraint on the target
 
-                    match physics.entity_set.get_collider_body_key(target_collider) {
-                        Some(body) => {
-                            let offset = physics.entity_set.get_body(body).unwrap().pose.inversed()
-                                * rope_start;
-                            physics.constraint_set.insert(
-                                sf::ConstraintBuilder::new(
-                                    rope.particles.first().expect("Rope had no particles").body,
-                                )
-                                .with_target(body)
-                                .with_target_origin(offset)
-                                .build_attachment(),
-                            );
-                        }
-                        None => {
-                            physics.constraint_set.insert(
-                                sf::ConstraintBuilder::new(
-                                    rope.particles.first().expect("Rope had no particles").body,
-                                )
-                                .with_target_origin(rope_start)
-                                .build_attachment(),
-                            );
-                        }
-                    }
+                    attach_to_target(
+                        physics,
+                        rope.particles.first().expect("Rope had no particles").body,
+                        target_collider,
+                        rope_start,
+                    );
 
                     let rope_key = physics.rope_set.insert(rope);
+                    self.vines.push(Vine {
+                        rope_key,
+                        anchors: [Some(target_collider), None],
+                    });
                     self.attached_vine = Some(AttachedVine {
                         rope_key,
                         player_constraint,
+                        length: (rope_end - player_pos).mag(),
+                        is_retracting: false,
                     });
 
                     // adjust player velocity towards the circle around the attachment point
@@ -427,9 +747,10 @@ impl PlayerController {
                         (-tangent, -tan_dot_vel)
                     };
 
-                    let dot_limit = sf::Angle::Deg(BOOST_ANGLE_LIMIT).rad().cos();
+                    let dot_limit = sf::Angle::Deg(tuning.boost_angle_limit).rad().cos();
                     if tan_dot_vel > dot_limit {
-                        player_body.velocity.linear = (BOOST_BONUS_SPEED + vel_mag) * *tangent;
+                        player_body.velocity.linear =
+                            (tuning.boost_bonus_speed + vel_mag) * *tangent;
                     }
                 }
                 //
@@ -438,6 +759,13 @@ impl PlayerController {
                 Some(attached) => {
                     physics.constraint_set.remove(attached.player_constraint);
                     self.attached_vine = None;
+                    if let Some(vine) = self
+                        .vines
+                        .iter_mut()
+                        .find(|vine| vine.rope_key == attached.rope_key)
+                    {
+                        vine.anchors[1] = Some(target_collider);
+                    }
 
                     let rope = physics.rope_set.get_mut(attached.rope_key)?;
                     let curr_end_body = physics
@@ -454,64 +782,747 @@ impl PlayerController {
                     rope.extend_line(dir, new_particle_count, &mut physics.entity_set);
 
                     // make the newly added part flammable and add visuals
-                    for &particle in rope.particles.iter().skip(old_particle_count) {
-                        let mesh = sf::Mesh::from(sf::ConvexMeshShape::Circle {
-                            r: rope.params.thickness / 2.0,
-                            points: 8,
-                        })
-                        .with_color([0.729, 0.855, 0.333, 1.0]);
-                        world.spawn((
-                            physics.entity_set.get_body(particle.body).unwrap().pose,
-                            particle.body,
-                            particle.collider,
-                            mesh,
-                            Flammable::default(),
-                        ));
+                    let prev_end_entity = hecs_sync
+                        .get_collider_entity(rope.particles[old_particle_count - 1].collider);
+                    let particle_entities = spawn_vine_particles(
+                        rope.particles
+                            .iter()
+                            .skip(old_particle_count)
+                            .map(|particle| {
+                                let pose = physics.entity_set.get_body(particle.body).unwrap().pose;
+                                (pose, particle.body, particle.collider)
+                            }),
+                        rope.params.thickness,
+                        self.fire_materials.read().flammable("vine"),
+                        world,
+                    );
+                    // connect the new part to the old one and to the new target for fire
+                    if let (Some(prev_end), Some(&first)) =
+                        (prev_end_entity, particle_entities.first())
+                    {
+                        fire::link(world, prev_end, first);
+                    }
+                    if let (Some(&last), Some(target)) = (
+                        particle_entities.last(),
+                        hecs_sync.get_collider_entity(target_collider),
+                    ) {
+                        fire::link(world, last, target);
                     }
 
                     // constraint on the new target
 
-                    match physics.entity_set.get_collider_body_key(target_collider) {
-                        Some(body_key) => {
-                            let body = physics.entity_set.get_body_mut(body_key).unwrap();
-                            let offset = new_segment_end - body.pose.translation;
-                            physics.constraint_set.insert(
-                                sf::ConstraintBuilder::new(
-                                    rope.particles.iter().last().unwrap().body,
-                                )
-                                .with_target(body_key)
-                                .with_target_origin(offset)
-                                .build_attachment(),
-                            );
-                        }
-                        None => {
-                            physics.constraint_set.insert(
-                                sf::ConstraintBuilder::new(
-                                    rope.particles.iter().last().unwrap().body,
-                                )
-                                .with_target_origin(new_segment_end)
-                                .build_attachment(),
-                            );
-                        }
+                    let end_body = rope.particles.iter().last().unwrap().body;
+                    attach_to_target(physics, end_body, target_collider, new_segment_end);
+                }
+            }
+        }
+
+        //
+        // retract held vine
+        //
+
+        // first press starts pulling the vine in,
+        // a second one lets go of it right away
+        if !was_aim_active
+            && (input.button(keys.retract_vine.into()) || gamepad.pressed(pad_keys.retract_vine))
+        {
+            match &mut self.attached_vine {
+                Some(attached) if !attached.is_retracting => attached.is_retracting = true,
+                Some(attached) => {
+                    physics
+                        .rope_set
+                        .remove(attached.rope_key, &mut physics.entity_set);
+                    self.attached_vine = None;
+                }
+                None => {}
+            }
+        }
+
+        //
+        // tether two things together
+        //
+
+        // the first end burned away or was otherwise removed before picking the second
+        if let Some(start) = self.tether_start {
+            if physics.entity_set.get_collider(start.collider).is_none() {
+                world.despawn(start.preview).ok();
+                self.tether_start = None;
+            }
+        }
+
+        // pick the first end, then the second.
+        // picking something that can't be attached to cancels,
+        // a second end that's too far or over the budget can be picked again
+        let aimed = match self.aim_target.validity {
+            AimTargetValidity::Valid { collider } => Some((self.aim_target.point, collider)),
+            _ => None,
+        };
+        if input.button(keys.tether.into()) || gamepad.pressed(pad_keys.tether) {
+            match (self.tether_start.take(), aimed) {
+                (None, Some((point, collider))) => {
+                    let preview = world.spawn((
+                        tether_preview_pose(point, point),
+                        tether_preview_mesh(0.0, true),
+                    ));
+                    self.tether_start = Some(TetherStart {
+                        point,
+                        collider,
+                        preview,
+                        preview_shown: (0, true),
+                    });
+                }
+                (Some(start), Some(end)) => {
+                    let start_end = (start.point, start.collider);
+                    if self.tether(start_end, end, &tuning, physics, hecs_sync, world) {
+                        world.despawn(start.preview).ok();
+                    } else {
+                        self.tether_start = Some(start);
                     }
                 }
+                (Some(start), None) => {
+                    world.despawn(start.preview).ok();
+                }
+                (None, None) => {}
+            }
+        }
+        // preview turns red when the tether can't be made
+        if let Some(mut start) = self.tether_start {
+            let end = self.aim_target.point;
+            let can_tether = aimed.is_some_and(|end| {
+                self.can_tether((start.point, start.collider), end, &tuning, physics)
+            });
+            let steps = ((end - start.point).mag() / TETHER_PREVIEW_STEP).round() as u32;
+            if let Ok((pose, mesh)) =
+                world.query_one_mut::<(&mut sf::Pose, &mut sf::Mesh)>(start.preview)
+            {
+                *pose = tether_preview_pose(start.point, end);
+                if start.preview_shown != (steps, can_tether) {
+                    *mesh = tether_preview_mesh(steps as f64 * TETHER_PREVIEW_STEP, can_tether);
+                    start.preview_shown = (steps, can_tether);
+                    self.tether_start = Some(start);
+                }
             }
         }
 
         //
-        // remove held vine
+        // dissolve vines
         //
-        if !self.is_aim_active && input.button(keys.retract_vine.into()) {
-            if let Some(attached) = self.attached_vine {
-                self.attached_vine = None;
-                // in the future, maybe "pull in" the vine particle by particle
-                // for a nice animation. for now, just delete it
+
+        if input.button(keys.dissolve_vine.into()) || gamepad.pressed(pad_keys.dissolve_vine) {
+            // the one being aimed at or the one attached to what's being aimed at,
+            // otherwise the oldest
+            let aimed_at = match self.aim_target.validity {
+                AimTargetValidity::Valid { collider } => self
+                    .vines
+                    .iter()
+                    .position(|vine| {
+                        physics.rope_set.get(vine.rope_key).is_some_and(|rope| {
+                            rope.particles.iter().any(|p| p.collider == collider)
+                        })
+                    })
+                    .or_else(|| {
+                        self.vines
+                            .iter()
+                            .position(|vine| vine.anchors.contains(&Some(collider)))
+                    }),
+                _ => None,
+            };
+            if let Some(idx) = aimed_at.or((!self.vines.is_empty()).then_some(0)) {
+                let vine = self.vines.remove(idx);
+                if self
+                    .attached_vine
+                    .is_some_and(|attached| attached.rope_key == vine.rope_key)
+                {
+                    self.attached_vine = None;
+                }
                 physics
                     .rope_set
-                    .remove(attached.rope_key, &mut physics.entity_set);
+                    .remove(vine.rope_key, &mut physics.entity_set);
+            }
+        }
+
+        //
+        // climb up and down the held vine
+        //
+
+        let dt = dt * self.time_scale(keys).unwrap_or(1.0);
+        match self.attached_vine {
+            Some(attached) if attached.is_retracting => {
+                self.change_vine_length(
+                    -dt * tuning.vine_retract_speed,
+                    player_body_key,
+                    physics,
+                    hecs_sync,
+                    world,
+                );
+            }
+            Some(attached) if target_dir.y != 0.0 => {
+                let rope = physics.rope_set.get(attached.rope_key)?;
+                let total_length =
+                    (rope.particles.len() - 1) as f64 * rope.params.spacing + attached.length;
+                // negative when descending
+                let max_descend = (total_length - tuning.rope_max_length)
+                    .max(-self.remaining_vine_length(physics));
+                let max_climb = (total_length - tuning.rope_min_length).max(0.0);
+                let climbed = target_dir.y.clamp(-1.0, 1.0) * tuning.vine_climb_speed * dt;
+                let climbed = climbed.clamp(max_descend.min(0.0), max_climb);
+                self.change_vine_length(-climbed, player_body_key, physics, hecs_sync, world);
+            }
+            _ => {}
+        }
+
+        //
+        // pick up and put down torches
+        //
+
+        let player_pos = world.get::<&sf::Pose>(entity).ok()?.translation;
+        if input.button(keys.interact.into()) || gamepad.pressed(pad_keys.interact) {
+            self.held_torch = match self.held_torch {
+                // let go and it falls from where it was held
+                Some(_) => None,
+                None => world
+                    .query_mut::<(&sf::Pose, &Torch)>()
+                    .into_iter()
+                    .map(|(torch, (pose, _))| (torch, (pose.translation - player_pos).mag()))
+                    .filter(|(_, dist)| *dist <= tuning.torch_pickup_range)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(torch, _)| torch),
+            };
+        }
+        if let Some(torch) = self.held_torch {
+            // held above the player's head
+            let held_pos = player_pos + sf::Vec2::new(0.0, tuning.coll_length / 2.0);
+            match world.query_one_mut::<(&mut sf::Pose, &sf::BodyKey)>(torch) {
+                Ok((torch_pose, &torch_body_key)) => {
+                    torch_pose.translation = held_pos;
+                    // don't let gravity build up speed while carried
+                    if let Some(torch_body) = physics.entity_set.get_body_mut(torch_body_key) {
+                        torch_body.velocity.linear = sf::Vec2::zero();
+                    }
+                }
+                Err(_) => self.held_torch = None,
             }
         }
 
         Some(())
     }
+
+    /// Whether a tether between two points on the given colliders is within the rope length limits,
+    /// fits in the vine budget, and both colliders still exist.
+    fn can_tether(
+        &self,
+        (start, start_collider): (sf::Vec2, sf::ColliderKey),
+        (end, end_collider): (sf::Vec2, sf::ColliderKey),
+        tuning: &PlayerTuning,
+        physics: &sf::PhysicsWorld,
+    ) -> bool {
+        let length = (end - start).mag();
+        let vine_length = line_vine_length(length, vine_rope_params().spacing);
+        (tuning.rope_min_length..=tuning.rope_max_length).contains(&length)
+            && self.vine_budget_allows(physics, 1, vine_length)
+            && [start_collider, end_collider]
+                .iter()
+                .all(|&coll| physics.entity_set.get_collider(coll).is_some())
+    }
+
+    /// Create a vine between two points on the given colliders,
+    /// attached to both without the player holding on to it.
+    /// Returns false without creating anything if `can_tether` doesn't allow it.
+    fn tether(
+        &mut self,
+        (start, start_collider): (sf::Vec2, sf::ColliderKey),
+        (end, end_collider): (sf::Vec2, sf::ColliderKey),
+        tuning: &PlayerTuning,
+        physics: &mut sf::PhysicsWorld,
+        hecs_sync: &sf::HecsSyncManager,
+        world: &mut sf::hecs::World,
+    ) -> bool {
+        if !self.can_tether(
+            (start, start_collider),
+            (end, end_collider),
+            tuning,
+            physics,
+        ) {
+            return false;
+        }
+
+        let rope = sf::Rope::spawn_line(vine_rope_params(), start, end, &mut physics.entity_set);
+        let particle_entities = spawn_vine_particles(
+            rope.particles.iter().map(|particle| {
+                let pose = physics.entity_set.get_body(particle.body).unwrap().pose;
+                (pose, particle.body, particle.collider)
+            }),
+            rope.params.thickness,
+            self.fire_materials.read().flammable("vine"),
+            world,
+        );
+        // carry fire to and from both ends
+        for (particle_entity, target_collider) in [
+            (particle_entities.first(), start_collider),
+            (particle_entities.last(), end_collider),
+        ] {
+            if let (Some(&particle_entity), Some(target)) = (
+                particle_entity,
+                hecs_sync.get_collider_entity(target_collider),
+            ) {
+                fire::link(world, particle_entity, target);
+            }
+        }
+
+        let first = rope.particles.first().expect("Rope had no particles").body;
+        let last = rope.particles.last().expect("Rope had no particles").body;
+        attach_to_target(physics, first, start_collider, start);
+        attach_to_target(physics, last, end_collider, end);
+
+        let rope_key = physics.rope_set.insert(rope);
+        self.vines.push(Vine {
+            rope_key,
+            anchors: [Some(start_collider), Some(end_collider)],
+        });
+        true
+    }
+
+    /// Whether creating the given number of vines with the given combined length
+    /// fits in the level's vine budget.
+    fn vine_budget_allows(
+        &self,
+        physics: &sf::PhysicsWorld,
+        new_vine_count: usize,
+        new_length: f64,
+    ) -> bool {
+        let used = self.used_vine_length(physics);
+        self.vine_budget
+            .allows(self.vines.len(), used, new_vine_count, new_length)
+    }
+
+    /// How much more vine can be created within the level's budget.
+    fn remaining_vine_length(&self, physics: &sf::PhysicsWorld) -> f64 {
+        self.vine_budget
+            .remaining_length(self.used_vine_length(physics))
+    }
+
+    /// Combined length of all the player's vines.
+    fn used_vine_length(&self, physics: &sf::PhysicsWorld) -> f64 {
+        self.vines
+            .iter()
+            .filter_map(|vine| physics.rope_set.get(vine.rope_key))
+            .map(|rope| vine_length(rope.particles.len(), rope.params.spacing))
+            .sum()
+    }
+
+    /// Make the attached vine longer or shorter by the given amount,
+    /// adding or removing particles at the player's end as needed.
+    /// If it gets shorter than the part held by the anchor, the whole vine is removed.
+    fn change_vine_length(
+        &mut self,
+        delta: f64,
+        player_body_key: sf::BodyKey,
+        physics: &mut sf::PhysicsWorld,
+        hecs_sync: &sf::HecsSyncManager,
+        world: &mut sf::hecs::World,
+    ) -> Option<()> {
+        let attached = self.attached_vine.as_mut()?;
+        let rope = physics.rope_set.get_mut(attached.rope_key)?;
+        attached.length += delta;
+
+        // shorten
+        while attached.length < 0.0 && rope.particles.len() > 1 {
+            let particle = rope.particles.pop()?;
+            // despawning the entity removes the particle from physics
+            if let Some(particle_entity) = hecs_sync.get_collider_entity(particle.collider) {
+                // the new end gets linked again if the vine is paid out
+                fire::unlink(world, particle_entity);
+                world.despawn(particle_entity).ok();
+            } else {
+                physics.entity_set.remove_collider(particle.collider);
+                physics.entity_set.remove_body(particle.body);
+            }
+            attached.length += rope.params.spacing;
+        }
+
+        if attached.length < 0.0 {
+            // reeled all the way in
+            physics
+                .rope_set
+                .remove(attached.rope_key, &mut physics.entity_set);
+            self.attached_vine = None;
+            return Some(());
+        }
+
+        // lengthen. only when paying out,
+        // the player can start further than one spacing away from the end
+        let mut new_particle_count = 0;
+        while delta > 0.0 && attached.length > rope.params.spacing {
+            attached.length -= rope.params.spacing;
+            new_particle_count += 1;
+        }
+        if new_particle_count > 0 {
+            let end = rope.particles.last()?;
+            let end_pos = physics.entity_set.get_body(end.body)?.pose.translation;
+            let player_body = physics.entity_set.get_body(player_body_key)?;
+            let to_player = player_body.pose.translation - end_pos;
+            let dir = if to_player.mag() > f64::EPSILON {
+                sf::Unit::new_normalize(to_player)
+            } else {
+                sf::Unit::new_unchecked(-sf::Vec2::unit_y())
+            };
+            let prev_end_entity = hecs_sync.get_collider_entity(end.collider);
+
+            let old_particle_count = rope.particles.len();
+            rope.extend_line(dir, new_particle_count, &mut physics.entity_set);
+            let particle_entities = spawn_vine_particles(
+                rope.particles
+                    .iter()
+                    .skip(old_particle_count)
+                    .map(|particle| {
+                        let pose = physics.entity_set.get_body(particle.body).unwrap().pose;
+                        (pose, particle.body, particle.collider)
+                    }),
+                rope.params.thickness,
+                self.fire_materials.read().flammable("vine"),
+                world,
+            );
+            if let (Some(prev_end), Some(&first)) = (prev_end_entity, particle_entities.first()) {
+                fire::link(world, prev_end, first);
+            }
+        }
+
+        // rebuild the player constraint with the new length and end particle
+        let end_body = rope.particles.last()?.body;
+        physics.constraint_set.remove(attached.player_constraint);
+        attached.player_constraint = physics.constraint_set.insert(
+            sf::ConstraintBuilder::new(player_body_key)
+                .with_target(end_body)
+                .with_limit(sf::ConstraintLimit::Lt)
+                .build_distance(attached.length),
+        );
+        Some(())
+    }
+}
+
+/// Spawn entities with visuals and flammability for newly created vine particles,
+/// linked to each other so that fire reliably travels along the vine.
+/// Returns the entities in the same order as the particles.
+fn spawn_vine_particles(
+    particles: impl Iterator<Item = (sf::Pose, sf::BodyKey, sf::ColliderKey)>,
+    thickness: f64,
+    flammable: Flammable,
+    world: &mut sf::hecs::World,
+) -> Vec<sf::hecs::Entity> {
+    let color = [0.729, 0.855, 0.333, 1.0];
+    let mesh_at_scale = move |scale: f64| {
+        sf::Mesh::from(sf::ConvexMeshShape::Circle {
+            r: scale * thickness / 2.0,
+            points: 8,
+        })
+    };
+    let entities: Vec<sf::hecs::Entity> = particles
+        .map(|(pose, body_key, coll_key)| {
+            let mesh = mesh_at_scale(1.0).with_color(color);
+            let visuals = BurnVisuals::new(color, mesh_at_scale);
+            world.spawn((pose, body_key, coll_key, mesh, flammable, visuals))
+        })
+        .collect();
+    for pair in entities.windows(2) {
+        fire::link(world, pair[0], pair[1]);
+    }
+    entities
+}
+
+/// Length of a vine with the given number of particles.
+/// Everything counted against the vine budget is measured with this.
+fn vine_length(particle_count: usize, spacing: f64) -> f64 {
+    particle_count.saturating_sub(1) as f64 * spacing
+}
+
+/// Length of the vine made by filling a straight line with particles,
+/// one per whole spacing like `Rope::extend_line` is used to add them.
+fn line_vine_length(dist: f64, spacing: f64) -> f64 {
+    vine_length((dist / spacing) as usize + 1, spacing)
+}
+
+fn vine_rope_params() -> sf::RopeParameters {
+    sf::RopeParameters {
+        bending_max_angle: sf::Angle::Deg(75.0).rad(),
+        bending_compliance: 0.05,
+        ..Default::default()
+    }
+}
+
+/// Attach a vine particle to a point on the given collider,
+/// moving along with the collider's body if it has one.
+fn attach_to_target(
+    physics: &mut sf::PhysicsWorld,
+    particle_body: sf::BodyKey,
+    target_collider: sf::ColliderKey,
+    point: sf::Vec2,
+) {
+    let builder = sf::ConstraintBuilder::new(particle_body);
+    let builder = match physics.entity_set.get_collider_body_key(target_collider) {
+        Some(body) => {
+            let offset = physics.entity_set.get_body(body).unwrap().pose.inversed() * point;
+            builder.with_target(body).with_target_origin(offset)
+        }
+        None => builder.with_target_origin(point),
+    };
+    physics.constraint_set.insert(builder.build_attachment());
+}
+
+/// Pose for the line previewing a tether between two points.
+fn tether_preview_pose(from: sf::Vec2, to: sf::Vec2) -> sf::Pose {
+    let dist = to - from;
+    sf::Pose::new(
+        (from + to) / 2.0,
+        sf::Angle::Rad(f64::atan2(dist.y, dist.x)).into(),
+    )
+}
+
+/// Line along the x axis previewing a tether, red if it can't be made.
+fn tether_preview_mesh(length: f64, can_tether: bool) -> sf::Mesh {
+    let color = if can_tether {
+        [0.729, 0.855, 0.333, 0.4]
+    } else {
+        [0.9, 0.2, 0.1, 0.4]
+    };
+    sf::Mesh::from(sf::Collider::new_capsule(length, 0.05)).with_color(color)
+}
+
+/// State of the slow-down-time-and-show-cool-reticle aiming mode.
+#[derive(Clone, Copy, Debug, Default)]
+struct AimState {
+    is_active: bool,
+    // cancel was pressed while holding shoot, so releasing it shouldn't shoot
+    is_shot_cancelled: bool,
+}
+
+impl AimState {
+    /// Update aim mode according to the player's chosen aim mode.
+    /// Returns true if a vine should be shot this tick.
+    fn update(&mut self, input: ShootInput, mode: AimMode) -> bool {
+        match mode {
+            // aim by holding shoot for long enough and shoot by releasing it,
+            // or shoot right away with a quick tap.
+            // cancel exits aim mode without shooting
+            // (and doesn't aim again until pressing shoot again)
+            AimMode::Hold => {
+                if input.pressed {
+                    self.is_shot_cancelled = false;
+                }
+                if input.held_for_delay && !self.is_shot_cancelled {
+                    self.is_active = true;
+                }
+                if input.cancel {
+                    self.is_active = false;
+                    self.is_shot_cancelled = true;
+                }
+                if input.released {
+                    self.is_active = false;
+                    return !self.is_shot_cancelled;
+                }
+                false
+            }
+            // press shoot once to aim and again to shoot
+            AimMode::Toggle => {
+                if input.cancel {
+                    self.is_active = false;
+                    return false;
+                }
+                if input.pressed {
+                    self.is_active = !self.is_active;
+                    return !self.is_active;
+                }
+                false
+            }
+            // shoot on press without slowing down time
+            AimMode::Instant => {
+                self.is_active = false;
+                input.pressed
+            }
+        }
+    }
+}
+
+/// Shoot button state for the current tick, combined from every input device.
+#[derive(Clone, Copy, Debug)]
+struct ShootInput {
+    pressed: bool,
+    held_for_delay: bool,
+    released: bool,
+    cancel: bool,
+}
+
+/// Find a direction near the given ray that hits something at least `min_length` away,
+/// trying directions further and further away from the original up to `aim_assist_angle`.
+fn aim_assist(
+    physics: &sf::PhysicsWorld,
+    tuning: &PlayerTuning,
+    ray: sf::Ray,
+    min_length: f64,
+) -> Option<sf::Ray> {
+    if tuning.aim_assist_steps == 0 {
+        return None;
+    }
+    let step = sf::Angle::Deg(tuning.aim_assist_angle).rad() / tuning.aim_assist_steps as f64;
+    std::iter::once(0.0)
+        .chain((1..=tuning.aim_assist_steps).flat_map(|i| [i as f64 * step, -(i as f64) * step]))
+        .map(|angle| {
+            let (sin, cos) = angle.sin_cos();
+            let dir = sf::Vec2::new(
+                ray.dir.x * cos - ray.dir.y * sin,
+                ray.dir.x * sin + ray.dir.y * cos,
+            );
+            sf::Ray {
+                start: ray.start,
+                dir: sf::Unit::new_normalize(dir),
+            }
+        })
+        .find(|&ray| {
+            physics
+                .spherecast(tuning.spherecast_radius, ray, tuning.rope_max_length)
+                .is_some_and(|hit| hit.t >= min_length)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONE: ShootInput = ShootInput {
+        pressed: false,
+        held_for_delay: false,
+        released: false,
+        cancel: false,
+    };
+    const PRESS: ShootInput = ShootInput {
+        pressed: true,
+        ..NONE
+    };
+    const HOLD: ShootInput = ShootInput {
+        held_for_delay: true,
+        ..NONE
+    };
+    const RELEASE: ShootInput = ShootInput {
+        released: true,
+        ..NONE
+    };
+    const CANCEL: ShootInput = ShootInput {
+        cancel: true,
+        ..NONE
+    };
+
+    /// Feed inputs to the aim state one tick at a time,
+    /// returning whether aim is active and whether to shoot after each.
+    fn run_aim(mode: AimMode, inputs: &[ShootInput]) -> Vec<(bool, bool)> {
+        let mut aim = AimState::default();
+        inputs
+            .iter()
+            .map(|&input| {
+                let shoot = aim.update(input, mode);
+                (aim.is_active, shoot)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hold_aim_shoots_on_tap_and_aims_while_held() {
+        assert_eq!(
+            run_aim(AimMode::Hold, &[PRESS, RELEASE]),
+            vec![(false, false), (false, true)]
+        );
+        assert_eq!(
+            run_aim(AimMode::Hold, &[PRESS, NONE, HOLD, NONE, RELEASE]),
+            vec![
+                (false, false),
+                (false, false),
+                (true, false),
+                (true, false),
+                (false, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn hold_aim_cancel_lasts_until_next_press() {
+        assert_eq!(
+            run_aim(
+                AimMode::Hold,
+                &[PRESS, HOLD, CANCEL, HOLD, RELEASE, PRESS, RELEASE]
+            ),
+            vec![
+                (false, false),
+                (true, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, false),
+                (false, true)
+            ]
+        );
+    }
+
+    #[test]
+    fn toggle_aim_shoots_on_second_press() {
+        assert_eq!(
+            run_aim(AimMode::Toggle, &[PRESS, RELEASE, PRESS, RELEASE]),
+            vec![(true, false), (true, false), (false, true), (false, false)]
+        );
+        assert_eq!(
+            run_aim(AimMode::Toggle, &[PRESS, CANCEL, PRESS]),
+            vec![(true, false), (false, false), (true, false)]
+        );
+    }
+
+    #[test]
+    fn instant_aim_shoots_on_press() {
+        assert_eq!(
+            run_aim(AimMode::Instant, &[PRESS, HOLD, RELEASE]),
+            vec![(false, true), (false, false), (false, false)]
+        );
+    }
+
+    #[test]
+    fn unlimited_budget_allows_anything() {
+        let budget = VineBudget::default();
+        assert!(budget.allows(100, 1000.0, 1, 1000.0));
+        assert_eq!(budget.remaining_length(1000.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn vine_count_is_limited() {
+        let budget = VineBudget {
+            max_vines: Some(2),
+            max_total_length: None,
+        };
+        assert!(budget.allows(1, 0.0, 1, 5.0));
+        assert!(!budget.allows(2, 0.0, 1, 5.0));
+        // extending a held vine doesn't add a new one
+        assert!(budget.allows(2, 0.0, 0, 5.0));
+    }
+
+    #[test]
+    fn total_length_is_limited() {
+        let budget = VineBudget {
+            max_vines: None,
+            max_total_length: Some(10.0),
+        };
+        assert_eq!(budget.remaining_length(4.0), 6.0);
+        assert!(budget.allows(1, 4.0, 1, 6.0));
+        assert!(!budget.allows(1, 4.0, 1, 6.5));
+        // never negative, even if more is used than the budget allows
+        assert_eq!(budget.remaining_length(12.0), 0.0);
+        assert!(!budget.allows(1, 12.0, 0, 0.1));
+    }
+
+    #[test]
+    fn new_vines_are_measured_like_existing_ones() {
+        let spacing = 0.5;
+        assert_eq!(vine_length(0, spacing), 0.0);
+        assert_eq!(vine_length(1, spacing), 0.0);
+        assert_eq!(vine_length(5, spacing), 2.0);
+        // partial spacings don't get a particle
+        assert_eq!(line_vine_length(2.2, spacing), 2.0);
+        assert_eq!(line_vine_length(0.3, spacing), 0.0);
+        // a line that's exactly n spacings long is a vine of n + 1 particles
+        assert_eq!(line_vine_length(1.5, spacing), vine_length(4, spacing));
+    }
 }
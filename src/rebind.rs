@@ -0,0 +1,121 @@
+//! Capturing key presses to rebind actions.
+
+use starframe::{
+    self as sf,
+    input::{Key, MouseButton},
+};
+
+use crate::settings::{Action, Binding};
+
+/// Keys that can be bound to actions.
+const BINDABLE_KEYS: &[Key] = &[
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::Key0,
+    Key::Escape,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::Left,
+    Key::Up,
+    Key::Right,
+    Key::Down,
+    Key::Space,
+    Key::Return,
+    Key::Back,
+    Key::Tab,
+    Key::Insert,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+    Key::LShift,
+    Key::RShift,
+    Key::LControl,
+    Key::RControl,
+    Key::LAlt,
+    Key::RAlt,
+    Key::Comma,
+    Key::Period,
+    Key::Slash,
+    Key::Backslash,
+    Key::Semicolon,
+    Key::Apostrophe,
+    Key::LBracket,
+    Key::RBracket,
+    Key::Minus,
+    Key::Equals,
+    Key::Grave,
+    Key::Numpad0,
+    Key::Numpad1,
+    Key::Numpad2,
+    Key::Numpad3,
+    Key::Numpad4,
+    Key::Numpad5,
+    Key::Numpad6,
+    Key::Numpad7,
+    Key::Numpad8,
+    Key::Numpad9,
+];
+
+const BINDABLE_MOUSE_BUTTONS: &[MouseButton] =
+    &[MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+/// Get the key or mouse button pressed this frame, if any,
+/// of the kind that the given action can be bound to.
+pub fn capture_binding(input: &sf::Input, action: Action) -> Option<Binding> {
+    if action.uses_mouse() {
+        BINDABLE_MOUSE_BUTTONS
+            .iter()
+            .find(|&&button| input.button(button.into()))
+            .map(|&button| Binding::Mouse(button))
+    } else {
+        BINDABLE_KEYS
+            .iter()
+            .find(|&&key| input.button(key.into()))
+            .map(|&key| Binding::Key(key))
+    }
+}
@@ -0,0 +1,279 @@
+//! Persistent per-user data: game progress and changes to settings.
+//!
+//! Everything is stored as JSON with a format version number
+//! in the user's data and config directories (following the XDG spec on Linux).
+//! Files from older versions are migrated on load,
+//! and files that can't be read are backed up and replaced with defaults
+//! so a broken save never prevents the game from starting.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::{Map, Value};
+
+use crate::settings::SettingsOverrides;
+
+const APP_DIR_NAME: &str = "flamegrower";
+const SAVE_FILE_NAME: &str = "save.json";
+const SETTINGS_FILE_NAME: &str = "settings.json";
+
+/// Functions converting each old version of a file to the next one.
+/// The version of a file is the number of migrations applied to it, plus one.
+type Migration = fn(&mut Map<String, Value>);
+
+const SAVE_MIGRATIONS: &[Migration] = &[];
+const SETTINGS_MIGRATIONS: &[Migration] = &[];
+
+/// The player's progress through the game.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    /// Asset ids of levels that can be selected.
+    pub unlocked_levels: BTreeSet<String>,
+    /// Where to continue from when starting the game.
+    pub checkpoint: Option<SavedCheckpoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SavedCheckpoint {
+    pub level: String,
+    /// Tiled object id of the spawn point, None to start from the beginning of the level.
+    pub spawn_point: Option<u32>,
+}
+
+impl SaveData {
+    /// Load the save file, or start a new one if there isn't one or it's unreadable.
+    pub fn load() -> Self {
+        match data_dir() {
+            Some(dir) => load_versioned(&dir.join(SAVE_FILE_NAME), SAVE_MIGRATIONS),
+            None => Self::default(),
+        }
+    }
+
+    pub fn store(&self) {
+        if let Some(dir) = data_dir() {
+            store_versioned(&dir.join(SAVE_FILE_NAME), self, SAVE_MIGRATIONS);
+        }
+    }
+}
+
+/// Load the user's changes to the default settings.
+pub fn load_settings_overrides() -> SettingsOverrides {
+    match config_dir() {
+        Some(dir) => load_versioned(&dir.join(SETTINGS_FILE_NAME), SETTINGS_MIGRATIONS),
+        None => SettingsOverrides::default(),
+    }
+}
+
+pub fn store_settings_overrides(overrides: &SettingsOverrides) {
+    if let Some(dir) = config_dir() {
+        store_versioned(
+            &dir.join(SETTINGS_FILE_NAME),
+            overrides,
+            SETTINGS_MIGRATIONS,
+        );
+    }
+}
+
+//
+// versioned files
+//
+
+fn load_versioned<T>(path: &Path, migrations: &[Migration]) -> T
+where
+    T: Default + serde::de::DeserializeOwned,
+{
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        // no file yet, not an error
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return T::default(),
+        Err(err) => {
+            eprintln!("failed to read {}: {err}", path.display());
+            return T::default();
+        }
+    };
+
+    match parse_versioned(&content, migrations) {
+        Ok(data) => data,
+        Err(err) => {
+            // keep the broken file around in case it can be fixed by hand
+            let backup = path.with_extension("json.corrupt");
+            eprintln!(
+                "{} is unreadable ({err}), moving it to {} and starting over",
+                path.display(),
+                backup.display()
+            );
+            fs::rename(path, &backup).ok();
+            T::default()
+        }
+    }
+}
+
+fn parse_versioned<T>(content: &[u8], migrations: &[Migration]) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    let current_version = migrations.len() as u64 + 1;
+
+    let mut file: Map<String, Value> =
+        serde_json::from_slice(content).map_err(|err| err.to_string())?;
+    let version = file
+        .remove("version")
+        .and_then(|v| v.as_u64())
+        .ok_or("missing version number")?;
+    if version == 0 || version > current_version {
+        return Err(format!("unsupported version {version}"));
+    }
+
+    for migration in &migrations[(version - 1) as usize..] {
+        migration(&mut file);
+    }
+
+    serde_json::from_value(Value::Object(file)).map_err(|err| err.to_string())
+}
+
+fn store_versioned<T: serde::Serialize>(path: &Path, data: &T, migrations: &[Migration]) {
+    let mut file = match serde_json::to_value(data) {
+        Ok(Value::Object(file)) => file,
+        _ => unreachable!("saved data must serialize into an object"),
+    };
+    file.insert("version".into(), (migrations.len() + 1).into());
+
+    // write to a temporary file first so a crash while writing
+    // doesn't leave a half-written save behind
+    let write = || -> std::io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec_pretty(&file)?)?;
+        fs::rename(&tmp_path, path)
+    };
+    if let Err(err) = write() {
+        eprintln!("failed to write {}: {err}", path.display());
+    }
+}
+
+//
+// platform directories
+//
+
+/// Directory for save data, e.g. `~/.local/share/flamegrower`.
+fn data_dir() -> Option<PathBuf> {
+    platform_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for settings, e.g. `~/.config/flamegrower`.
+fn config_dir() -> Option<PathBuf> {
+    platform_dir("XDG_CONFIG_HOME", ".config")
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn platform_dir(xdg_var: &str, home_fallback: &str) -> Option<PathBuf> {
+    let base = match std::env::var_os(xdg_var) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(home_fallback),
+    };
+    Some(base.join(APP_DIR_NAME))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_dir(_xdg_var: &str, _home_fallback: &str) -> Option<PathBuf> {
+    let home = PathBuf::from(std::env::var_os("HOME")?);
+    Some(home.join("Library/Application Support").join(APP_DIR_NAME))
+}
+
+#[cfg(windows)]
+fn platform_dir(_xdg_var: &str, _home_fallback: &str) -> Option<PathBuf> {
+    Some(PathBuf::from(std::env::var_os("APPDATA")?).join(APP_DIR_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Data {
+        name: String,
+        count: u32,
+    }
+
+    // version 1 called `count` `amount`, version 2 added `name`
+    const MIGRATIONS: &[Migration] = &[rename_amount, add_name];
+
+    fn rename_amount(file: &mut Map<String, Value>) {
+        if let Some(amount) = file.remove("amount") {
+            file.insert("count".into(), amount);
+        }
+    }
+
+    fn add_name(file: &mut Map<String, Value>) {
+        file.entry("name").or_insert_with(|| "unnamed".into());
+    }
+
+    fn parse(content: &str) -> Result<Data, String> {
+        parse_versioned(content.as_bytes(), MIGRATIONS)
+    }
+
+    #[test]
+    fn current_version_is_read_as_is() {
+        assert_eq!(
+            parse(r#"{ "version": 3, "name": "a", "count": 2 }"#),
+            Ok(Data {
+                name: "a".into(),
+                count: 2
+            })
+        );
+    }
+
+    #[test]
+    fn old_versions_are_migrated() {
+        let expected = Ok(Data {
+            name: "unnamed".into(),
+            count: 5,
+        });
+        assert_eq!(parse(r#"{ "version": 1, "amount": 5 }"#), expected);
+        assert_eq!(parse(r#"{ "version": 2, "count": 5 }"#), expected);
+        // migrations before the file's version aren't applied
+        assert!(parse(r#"{ "version": 2, "amount": 5 }"#).is_err());
+    }
+
+    #[test]
+    fn unsupported_files_are_errors() {
+        assert!(parse(r#"{ "name": "a", "count": 2 }"#).is_err());
+        assert!(parse(r#"{ "version": 0, "name": "a", "count": 2 }"#).is_err());
+        assert!(parse(r#"{ "version": 4, "name": "a", "count": 2 }"#).is_err());
+        assert!(parse(r#"[1, 2, 3]"#).is_err());
+        assert!(parse("not json").is_err());
+    }
+
+    #[test]
+    fn saved_checkpoints_are_read() {
+        let save: SaveData = parse_versioned(
+            br#"{
+                "version": 1,
+                "unlocked_levels": ["scenes.test"],
+                "checkpoint": { "level": "scenes.test", "spawn_point": null }
+            }"#,
+            SAVE_MIGRATIONS,
+        )
+        .unwrap();
+        assert_eq!(
+            save.checkpoint,
+            Some(SavedCheckpoint {
+                level: "scenes.test".into(),
+                spawn_point: None,
+            })
+        );
+
+        let save: SaveData = parse_versioned(
+            br#"{ "version": 1, "checkpoint": { "level": "scenes.test", "spawn_point": 3 } }"#,
+            SAVE_MIGRATIONS,
+        )
+        .unwrap();
+        assert_eq!(save.checkpoint.unwrap().spawn_point, Some(3));
+    }
+}
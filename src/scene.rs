@@ -1,12 +1,25 @@
 use starframe as sf;
 
-use assets_manager::{loader, Asset};
+use assets_manager::Asset;
 
 use crate::{
-    fire::{Flammable, FlammableParams},
-    player::PlayerSpawnPoint,
+    collision_layers,
+    fire::{
+        self, heat_source_mesh, BurnVisuals, FireMaterials, HeatSource, Insulator, Lens,
+        PressureSwitch, TimedIgniter, Torch, WetArea, Wetting, HEAT_SOURCE_RADIUS,
+    },
+    goals::{BurnTarget, GoalKind, LevelGoal},
+    levels::LevelExit,
+    player::{Hazard, PlayerSpawnPoint, VineBudget},
+    ASSETS,
 };
 
+mod polygon;
+mod tiled;
+pub use tiled::parse_scene;
+mod validate;
+use validate::ObjectRef;
+
 /// Default physics material should allow player to push boxes
 /// but also rotate large ones by grabbing a high corner and pulling down
 const DEFAULT_PHYSICS_MATERIAL: sf::PhysicsMaterial = sf::PhysicsMaterial {
@@ -19,23 +32,56 @@ const DEFAULT_PHYSICS_MATERIAL: sf::PhysicsMaterial = sf::PhysicsMaterial {
 
 const DEFAULT_BODY_DENSITY: f64 = 0.25;
 
+/// Radius of the capsules making up the outline of static polygons.
+const POLYGON_EDGE_RADIUS: f64 = 0.05;
+
+/// Thickness of the ray coming out of lenses.
+const LENS_RAY_RADIUS: f64 = 0.1;
+
+/// Mass of a torch when dropped, light enough not to flatten things it lands on.
+const TORCH_MASS: f64 = 0.1;
+
+/// Distance below the lowest object in a scene
+/// where the player dies if the scene doesn't set `kill_plane_y`.
+const KILL_PLANE_MARGIN: f64 = 10.0;
+
 /// A scene created with the Tiled editor.
 ///
-/// Raw tiled scenes need to be run through `export.jq` to parse correctly.
-/// See `export-scene` in `justfile`.
+/// Loaded directly from Tiled's JSON map format, see `tiled.rs`
+/// for the transformations applied to the raw data.
 #[derive(Clone, Debug, Default, serde::Deserialize)]
 #[serde(default)]
 pub struct Scene {
     // temporary hack to see the whole level before I have proper camera control
     initial_camera_zoom: f64,
+    /// Height below which the player dies.
+    /// Defaults to a bit below the lowest object in the scene.
+    kill_plane_y: Option<f64>,
+    goal: GoalKind,
+    /// Seconds to complete the goal in, no limit if not set.
+    time_limit: Option<f64>,
+    /// Number of vines the player can have at once, no limit if not set.
+    max_vines: Option<usize>,
+    /// Combined length of all the player's vines, no limit if not set.
+    max_vine_length: Option<f64>,
+    /// Filled in from the map's objects by the loader, together with `sources`.
+    #[serde(skip)]
     recipes: Vec<Recipe>,
+    /// Tiled objects each recipe came from, for error reporting.
+    #[serde(skip)]
+    sources: Vec<ObjectRef>,
 }
 impl Asset for Scene {
-    const EXTENSION: &'static str = "json";
+    const EXTENSIONS: &'static [&'static str] = &["tmj", "json"];
 
-    type Loader = loader::JsonLoader;
+    type Loader = tiled::TiledLoader;
 }
 
+/// Component holding the Tiled object id of the object an entity was spawned from,
+/// so objects can refer to each other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjectId(pub u32);
+
 impl Scene {
     pub fn instantiate(
         &self,
@@ -45,8 +91,62 @@ impl Scene {
     ) {
         camera.transform.scale = self.initial_camera_zoom;
 
-        for recipe in self.recipes.iter() {
-            recipe.spawn(physics, world);
+        let materials = ASSETS
+            .load::<FireMaterials>("fire_materials")
+            .expect("Missing or invalid fire materials");
+        let materials = materials.read();
+        for (recipe, source) in self.recipes_with_sources() {
+            for entity in recipe.spawn(physics, world, &materials) {
+                world.insert_one(entity, ObjectId(source.id)).ok();
+            }
+        }
+    }
+
+    /// Each recipe along with the Tiled object it came from.
+    fn recipes_with_sources(&self) -> impl Iterator<Item = (&Recipe, &ObjectRef)> {
+        assert_eq!(
+            self.recipes.len(),
+            self.sources.len(),
+            "every recipe needs the object it came from"
+        );
+        self.recipes.iter().zip(&self.sources)
+    }
+
+    pub fn goal(&self) -> LevelGoal {
+        LevelGoal {
+            kind: self.goal,
+            time_limit: self.time_limit,
+        }
+    }
+
+    pub fn vine_budget(&self) -> VineBudget {
+        VineBudget {
+            max_vines: self.max_vines,
+            max_total_length: self.max_vine_length,
+        }
+    }
+
+    pub fn kill_plane_y(&self) -> f64 {
+        if let Some(y) = self.kill_plane_y {
+            return y;
+        }
+        let lowest_point = self
+            .recipes
+            .iter()
+            .map(|recipe| {
+                let pose_y = recipe.pose().0.translation.y;
+                match recipe {
+                    Recipe::StaticCapsuleChain { polyline, .. } => {
+                        polyline.iter().map(|p| pose_y + p.y).fold(pose_y, f64::min)
+                    }
+                    _ => pose_y,
+                }
+            })
+            .fold(f64::INFINITY, f64::min);
+        if lowest_point.is_finite() {
+            lowest_point - KILL_PLANE_MARGIN
+        } else {
+            -KILL_PLANE_MARGIN
         }
     }
 }
@@ -70,24 +170,52 @@ pub enum Recipe {
         pose: TiledPose,
         #[serde(flatten)]
         collider: TiledCollider,
+        /// Fire material name, makes the object flammable if set.
+        material: Option<String>,
+        /// Blocks fire from spreading through the object.
+        #[serde(default = "false_")]
+        insulating: bool,
+        /// Needs to be burned for the level's `BurnAllTargets` goal.
+        #[serde(default = "false_")]
+        burn_target: bool,
     },
     //
     // interactive stuff
     //
+    /// Spawn point that also works as a checkpoint.
+    /// The one with the lowest `checkpoint_order` is where the level starts.
     PlayerSpawnPoint {
         pose: TiledPose,
+        /// Tiled object id, used to remember the active checkpoint across scene resets.
+        id: u32,
+        #[serde(default)]
+        checkpoint_order: i32,
+        /// Size of the area that activates the checkpoint when touched.
+        #[serde(default = "default_checkpoint_radius")]
+        radius: f64,
     },
     PhysicsObject {
         pose: TiledPose,
         #[serde(flatten)]
         collider: TiledCollider,
+        /// Fire material name, makes the object flammable if set.
+        material: Option<String>,
+        /// Blocks fire from spreading through the object.
+        #[serde(default = "false_")]
+        insulating: bool,
+        /// Needs to be burned for the level's `BurnAllTargets` goal.
+        #[serde(default = "false_")]
+        burn_target: bool,
     },
+    /// The invasive alien plant, always a `BurnTarget`.
     Weed {
         pose: TiledPose,
         #[serde(flatten)]
         collider: TiledCollider,
         #[serde(default = "true_")]
         is_static: bool,
+        #[serde(default = "default_weed_material")]
+        material: String,
     },
     Flamevine {
         pose: TiledPose,
@@ -95,11 +223,129 @@ pub enum Recipe {
         collider: TiledCollider,
         #[serde(default = "true_")]
         is_static: bool,
+        #[serde(default = "default_flamevine_material")]
+        material: String,
+    },
+    LevelExit {
+        pose: TiledPose,
+        #[serde(flatten)]
+        collider: TiledCollider,
+    },
+    /// Puts out fires and soaks anything flammable inside it.
+    Water {
+        pose: TiledPose,
+        #[serde(flatten)]
+        collider: TiledCollider,
+    },
+    /// Gradually makes flammable things inside it harder to ignite.
+    WetZone {
+        pose: TiledPose,
+        #[serde(flatten)]
+        collider: TiledCollider,
+        /// Wetness added per second, 1 being completely soaked.
+        #[serde(default = "default_wetting_rate")]
+        rate: f64,
+    },
+    //
+    // heat sources
+    //
+    /// Heat source the player can pick up and carry.
+    Torch {
+        pose: TiledPose,
+        #[serde(default = "default_torch_heat")]
+        heat: f64,
+        #[serde(default = "default_heat_radius")]
+        radius: f64,
+    },
+    /// Heat source that turns on after a delay.
+    TimedIgniter {
+        pose: TiledPose,
+        delay: f64,
+        /// How long to stay on, forever if not set.
+        duration: Option<f64>,
+        #[serde(default = "default_torch_heat")]
+        heat: f64,
+        #[serde(default = "default_heat_radius")]
+        radius: f64,
+    },
+    /// Plate that ignites the target object when a physics object is pushed onto it.
+    PressureSwitch {
+        pose: TiledPose,
+        #[serde(flatten)]
+        collider: TiledCollider,
+        /// Tiled object id of the thing to ignite.
+        target: u32,
+    },
+    /// Focuses light into a ray along its rotation, heating up whatever it hits.
+    Lens {
+        pose: TiledPose,
+        #[serde(default = "default_lens_heat")]
+        heat: f64,
+        #[serde(default = "default_lens_range")]
+        range: f64,
+    },
+    /// Spikes, kill zones etc. that kill the player on touch.
+    Hazard {
+        pose: TiledPose,
+        #[serde(flatten)]
+        collider: TiledCollider,
     },
 }
 
 impl Recipe {
-    pub fn spawn(&self, physics: &mut sf::PhysicsWorld, world: &mut sf::hecs::World) {
+    pub fn pose(&self) -> TiledPose {
+        match self {
+            Recipe::StaticCapsuleChain { pose, .. }
+            | Recipe::StaticCollider { pose, .. }
+            | Recipe::PlayerSpawnPoint { pose, .. }
+            | Recipe::PhysicsObject { pose, .. }
+            | Recipe::Weed { pose, .. }
+            | Recipe::Flamevine { pose, .. }
+            | Recipe::LevelExit { pose, .. }
+            | Recipe::Water { pose, .. }
+            | Recipe::WetZone { pose, .. }
+            | Recipe::Torch { pose, .. }
+            | Recipe::TimedIgniter { pose, .. }
+            | Recipe::PressureSwitch { pose, .. }
+            | Recipe::Lens { pose, .. }
+            | Recipe::Hazard { pose, .. } => *pose,
+        }
+    }
+
+    /// Name of the fire material the recipe uses, if it's flammable.
+    pub fn material(&self) -> Option<&str> {
+        match self {
+            Recipe::StaticCollider { material, .. } | Recipe::PhysicsObject { material, .. } => {
+                material.as_deref()
+            }
+            Recipe::Weed { material, .. } | Recipe::Flamevine { material, .. } => Some(material),
+            _ => None,
+        }
+    }
+
+    /// The Tiled shape the recipe is made from, if it has one.
+    pub fn collider(&self) -> Option<&TiledCollider> {
+        match self {
+            Recipe::StaticCollider { collider, .. }
+            | Recipe::PhysicsObject { collider, .. }
+            | Recipe::Weed { collider, .. }
+            | Recipe::Flamevine { collider, .. }
+            | Recipe::LevelExit { collider, .. }
+            | Recipe::Water { collider, .. }
+            | Recipe::WetZone { collider, .. }
+            | Recipe::PressureSwitch { collider, .. }
+            | Recipe::Hazard { collider, .. } => Some(collider),
+            _ => None,
+        }
+    }
+
+    pub fn spawn(
+        &self,
+        physics: &mut sf::PhysicsWorld,
+        world: &mut sf::hecs::World,
+        materials: &FireMaterials,
+    ) -> Vec<sf::hecs::Entity> {
+        let mut entities = Vec::new();
         match self {
             //
             // world geometry
@@ -122,80 +368,228 @@ impl Recipe {
                     let coll = sf::Collider::new_capsule(len, r);
                     let coll_key = physics.entity_set.insert_collider(coll);
                     let mesh = sf::Mesh::from(coll).with_color([1.0; 4]);
-                    world.spawn((pose, coll_key, mesh));
+                    entities.push(world.spawn((pose, coll_key, mesh)));
                 }
             }
-            Recipe::StaticCollider { pose, collider } => {
-                let coll = collider.generate_collider();
-                let coll_key = physics.entity_set.insert_collider(coll);
-                let color = [1.0; 4];
-                let mesh = sf::Mesh::from(coll).with_color(color);
-                world.spawn((pose.0, coll_key, mesh));
+            Recipe::StaticCollider {
+                pose,
+                collider,
+                material,
+                insulating,
+                burn_target,
+            } => {
+                for piece in collider.insert_solid(pose.0, None, [1.0; 4], physics) {
+                    let entity = world.spawn((piece.pose, piece.coll_key, piece.mesh));
+                    entities.push(entity);
+                    if let Some(material) = material {
+                        world
+                            .insert(entity, (materials.flammable(material), piece.visuals))
+                            .ok();
+                    }
+                    if *insulating {
+                        world.insert_one(entity, Insulator).ok();
+                    }
+                    if *burn_target {
+                        world.insert_one(entity, BurnTarget).ok();
+                    }
+                }
+                fire::burn_together(world, &entities);
             }
             //
             // interactive stuff
             //
-            Recipe::PlayerSpawnPoint { pose } => {
-                world.spawn((pose.0, PlayerSpawnPoint));
+            Recipe::PlayerSpawnPoint {
+                pose,
+                id,
+                checkpoint_order,
+                radius,
+            } => {
+                let coll = sf::Collider::new_circle(*radius)
+                    .sensor()
+                    .with_layer(collision_layers::INTERACTABLE);
+                let coll_key = physics.entity_set.insert_collider(coll);
+                entities.push(world.spawn((
+                    pose.0,
+                    coll_key,
+                    PlayerSpawnPoint {
+                        id: *id,
+                        order: *checkpoint_order,
+                    },
+                )));
             }
-            Recipe::PhysicsObject { pose, collider } => {
-                let coll = collider.generate_collider();
-                let body = sf::Body::new_dynamic(coll.info(), DEFAULT_BODY_DENSITY);
-                let body_key = physics.entity_set.insert_body(body);
-                let coll_key = physics.entity_set.attach_collider(body_key, coll);
-                let mesh = sf::Mesh::from(coll).with_color([0.2, 0.6, 0.9, 1.0]);
-                world.spawn((pose.0, body_key, coll_key, mesh));
+            Recipe::PhysicsObject {
+                pose,
+                collider,
+                material,
+                insulating,
+                burn_target,
+            } => {
+                let density = Some(DEFAULT_BODY_DENSITY);
+                let color = [0.2, 0.6, 0.9, 1.0];
+                for piece in collider.insert_solid(pose.0, density, color, physics) {
+                    let entity = world.spawn((piece.pose, piece.coll_key, piece.mesh));
+                    entities.push(entity);
+                    if let Some(body_key) = piece.body_key {
+                        world.insert_one(entity, body_key).ok();
+                    }
+                    if let Some(material) = material {
+                        world
+                            .insert(entity, (materials.flammable(material), piece.visuals))
+                            .ok();
+                    }
+                    if *insulating {
+                        world.insert_one(entity, Insulator).ok();
+                    }
+                    if *burn_target {
+                        world.insert_one(entity, BurnTarget).ok();
+                    }
+                }
             }
             Recipe::Weed {
                 pose,
                 collider,
                 is_static,
+                material,
             } => {
-                let coll = collider.generate_collider();
-                let coll_key = physics.entity_set.insert_collider(coll);
-                let mesh = sf::Mesh::from(coll).with_color([0.2, 0.08, 0.4, 1.0]);
-                let flammable = Flammable::new(FlammableParams {
-                    time_to_destroy: Some(0.5),
-                    ..Default::default()
-                });
-
-                let entity = world.spawn((pose.0, coll_key, mesh, flammable));
-
-                if !is_static {
-                    let body = sf::Body::new_dynamic(coll.info(), DEFAULT_BODY_DENSITY);
-                    let body_key = physics.entity_set.insert_body(body);
-                    physics
-                        .entity_set
-                        .attach_existing_collider(body_key, coll_key);
-                    world.insert_one(entity, body_key).ok();
+                let density = (!is_static).then_some(DEFAULT_BODY_DENSITY);
+                let color = [0.2, 0.08, 0.4, 1.0];
+                for piece in collider.insert_solid(pose.0, density, color, physics) {
+                    let flammable = materials.flammable(material);
+                    let entity = world.spawn((
+                        piece.pose,
+                        piece.coll_key,
+                        piece.mesh,
+                        flammable,
+                        piece.visuals,
+                        BurnTarget,
+                    ));
+                    entities.push(entity);
+                    if let Some(body_key) = piece.body_key {
+                        world.insert_one(entity, body_key).ok();
+                    }
                 }
+                fire::burn_together(world, &entities);
             }
             Recipe::Flamevine {
                 pose,
                 collider,
                 is_static,
+                material,
             } => {
-                let coll = collider.generate_collider();
-                let coll_key = physics.entity_set.insert_collider(coll);
-                let mesh = sf::Mesh::from(coll).with_color([0.9, 0.3, 0.0, 1.0]);
-                let eternal_fire = Flammable::new(FlammableParams {
-                    time_to_destroy: None,
-                    ..Default::default()
-                })
-                .ignited();
-
-                let entity = world.spawn((pose.0, coll_key, mesh, eternal_fire));
-
-                if !is_static {
-                    let body = sf::Body::new_dynamic(coll.info(), 1.0);
-                    let body_key = physics.entity_set.insert_body(body);
-                    physics
-                        .entity_set
-                        .attach_existing_collider(body_key, coll_key);
-                    world.insert_one(entity, body_key).ok();
+                let density = (!is_static).then_some(1.0);
+                let color = [0.9, 0.3, 0.0, 1.0];
+                for piece in collider.insert_solid(pose.0, density, color, physics) {
+                    let eternal_fire = materials.flammable(material).ignited();
+                    let entity = world.spawn((
+                        piece.pose,
+                        piece.coll_key,
+                        piece.mesh,
+                        eternal_fire,
+                        piece.visuals,
+                    ));
+                    entities.push(entity);
+                    if let Some(body_key) = piece.body_key {
+                        world.insert_one(entity, body_key).ok();
+                    }
                 }
+                fire::burn_together(world, &entities);
+            }
+            Recipe::LevelExit { pose, collider } => {
+                let (pose, coll_key, coll) = collider.insert_interactable(pose.0, physics);
+                let mesh = sf::Mesh::from(coll).with_color([0.3, 0.9, 0.5, 0.3]);
+                entities.push(world.spawn((pose, coll_key, mesh, LevelExit)));
+            }
+            Recipe::Water { pose, collider } => {
+                let (pose, coll) = collider.generate_collider(pose.0);
+                let mesh = sf::Mesh::from(coll).with_color([0.1, 0.3, 0.8, 0.5]);
+                let area = WetArea {
+                    collider: coll,
+                    wetting: Wetting::Submerge,
+                };
+                entities.push(world.spawn((pose, mesh, area)));
+            }
+            Recipe::WetZone {
+                pose,
+                collider,
+                rate,
+            } => {
+                let (pose, coll) = collider.generate_collider(pose.0);
+                let mesh = sf::Mesh::from(coll).with_color([0.3, 0.5, 0.8, 0.2]);
+                let area = WetArea {
+                    collider: coll,
+                    wetting: Wetting::Dampen(*rate),
+                };
+                entities.push(world.spawn((pose, mesh, area)));
+            }
+            //
+            // heat sources
+            //
+            Recipe::Torch { pose, heat, radius } => {
+                let source = HeatSource {
+                    heat: *heat,
+                    radius: *radius,
+                    is_active: true,
+                };
+                // dynamic so that it falls when the player puts it down
+                let body = sf::Body::new_particle(TORCH_MASS);
+                let body_key = physics.entity_set.insert_body(body);
+                let coll = sf::Collider::new_circle(HEAT_SOURCE_RADIUS)
+                    .with_layer(collision_layers::TORCH);
+                let coll_key = physics.entity_set.attach_collider(body_key, coll);
+                let mesh = heat_source_mesh(true);
+                entities.push(world.spawn((pose.0, body_key, coll_key, mesh, source, Torch)));
+            }
+            Recipe::TimedIgniter {
+                pose,
+                delay,
+                duration,
+                heat,
+                radius,
+            } => {
+                let source = HeatSource {
+                    heat: *heat,
+                    radius: *radius,
+                    is_active: false,
+                };
+                let igniter = TimedIgniter {
+                    delay: *delay,
+                    duration: *duration,
+                    elapsed: 0.0,
+                };
+                entities.push(world.spawn((pose.0, heat_source_mesh(false), source, igniter)));
+            }
+            Recipe::PressureSwitch {
+                pose,
+                collider,
+                target,
+            } => {
+                let (pose, coll) = collider.generate_collider(pose.0);
+                let coll = coll.sensor().with_layer(collision_layers::PRESSURE_SWITCH);
+                let coll_key = physics.entity_set.insert_collider(coll);
+                let mesh = sf::Mesh::from(coll).with_color([0.5, 0.4, 0.3, 1.0]);
+                let switch = PressureSwitch {
+                    target: *target,
+                    is_pressed: false,
+                };
+                entities.push(world.spawn((pose, coll_key, mesh, switch)));
+            }
+            Recipe::Lens { pose, heat, range } => {
+                let mesh = sf::Mesh::from(sf::Collider::new_capsule(0.1, 0.3))
+                    .with_color([0.6, 0.9, 1.0, 0.8]);
+                let lens = Lens {
+                    heat: *heat,
+                    range: *range,
+                    radius: LENS_RAY_RADIUS,
+                };
+                entities.push(world.spawn((pose.0, mesh, lens)));
+            }
+            Recipe::Hazard { pose, collider } => {
+                let (pose, coll_key, coll) = collider.insert_interactable(pose.0, physics);
+                let mesh = sf::Mesh::from(coll).with_color([0.8, 0.1, 0.1, 1.0]);
+                entities.push(world.spawn((pose, coll_key, mesh, Hazard)));
             }
         }
+        entities
     }
 }
 
@@ -231,11 +625,13 @@ impl From<TiledPose> for sf::Pose {
     }
 }
 
-/// Non-polygon shapes produced by Tiled.
+/// Shapes produced by Tiled.
 /// Symmetric shapes are sized based on width.
+/// If the object is a polygon, `shape` is ignored and the polygon is used instead,
+/// see `generate_collider` and `generate_outline` for how it turns into colliders.
 ///
 /// Use with `#[serde(flatten)]` in recipes.
-#[derive(Clone, Copy, Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize)]
 pub struct TiledCollider {
     width: f64,
     height: f64,
@@ -243,6 +639,8 @@ pub struct TiledCollider {
     shape: TiledColliderShape,
     #[serde(default)]
     corner_radius: f64,
+    #[serde(default)]
+    polygon: Option<Vec<sf::Vec2>>,
 }
 
 #[derive(Clone, Copy, Debug, serde::Deserialize)]
@@ -273,16 +671,215 @@ impl TiledColliderShape {
 }
 
 impl TiledCollider {
-    pub fn generate_collider(&self) -> sf::Collider {
-        let mut coll = self
-            .shape
-            .generate_collider(self.width, self.height)
-            .with_material(DEFAULT_PHYSICS_MATERIAL);
+    /// Generate a single collider for the shape,
+    /// along with the pose to put it at given the pose of the object.
+    ///
+    /// Starframe has no collider for arbitrary polygons,
+    /// so polygons are approximated with their bounding box here,
+    /// centered on the box instead of the object's origin.
+    /// Use this for everything that needs an inside: dynamic bodies, sensors and areas.
+    pub fn generate_collider(&self, pose: sf::Pose) -> (sf::Pose, sf::Collider) {
+        let Some(points) = &self.polygon else {
+            let coll = self.shape.generate_collider(self.width, self.height);
+            return (pose, self.finish_collider(coll));
+        };
+        let mut min = sf::Vec2::broadcast(f64::INFINITY);
+        let mut max = sf::Vec2::broadcast(f64::NEG_INFINITY);
+        for p in points {
+            min = min.min_by_component(*p);
+            max = max.max_by_component(*p);
+        }
+        let size = max - min;
+        let pose = sf::Pose::new(pose * ((min + max) / 2.0), pose.rotation);
+        let coll = sf::Collider::new_rect(size.x, size.y);
+        (pose, self.finish_collider(coll))
+    }
+
+    /// Generate the colliders for a piece of solid static geometry,
+    /// along with the poses to put them at given the pose of the object.
+    ///
+    /// Polygons become a closed chain of thin capsules along their outline,
+    /// the same way a `StaticCapsuleChain` is built.
+    /// Other shapes generate exactly one collider, same as `generate_collider`.
+    pub fn generate_outline(&self, pose: sf::Pose) -> Vec<(sf::Pose, sf::Collider)> {
+        let Some(points) = &self.polygon else { return vec![self.generate_collider(pose)] };
+        let points = polygon::without_redundant_points(points);
+        // two points make a single segment, not a closed loop
+        let edge_count = if points.len() >= 3 {
+            points.len()
+        } else {
+            points.len().saturating_sub(1)
+        };
+        (0..edge_count)
+            .map(|i| {
+                let (a, b) = (pose * points[i], pose * points[(i + 1) % points.len()]);
+                let dist = b - a;
+                let edge_pose = sf::Pose::new(
+                    (a + b) / 2.0,
+                    sf::Angle::Rad(f64::atan2(dist.y, dist.x)).into(),
+                );
+                let coll = sf::Collider::new_capsule(dist.mag(), POLYGON_EDGE_RADIUS)
+                    .with_material(DEFAULT_PHYSICS_MATERIAL);
+                (edge_pose, coll)
+            })
+            .collect()
+    }
+
+    /// A copy of this shape scaled around its origin.
+    pub fn scaled(&self, scale: f64) -> Self {
+        Self {
+            width: self.width * scale,
+            height: self.height * scale,
+            shape: self.shape,
+            corner_radius: self.corner_radius * scale,
+            polygon: self
+                .polygon
+                .as_ref()
+                .map(|points| points.iter().map(|p| *p * scale).collect()),
+        }
+    }
+
+    /// Burn visuals for the collider generated by `generate_collider`,
+    /// shrinking it around its center.
+    pub fn burn_visuals(&self, color: [f32; 4]) -> BurnVisuals {
+        let shape = self.clone();
+        BurnVisuals::new(color, move |scale| {
+            sf::Mesh::from(shape.scaled(scale).generate_collider(origin_pose()).1)
+        })
+    }
+
+    /// Burn visuals for one of the colliders generated by `generate_outline`,
+    /// shrinking it around its own center.
+    pub fn outline_burn_visuals(
+        &self,
+        piece: usize,
+        coll: sf::Collider,
+        color: [f32; 4],
+    ) -> BurnVisuals {
+        let shape = self.clone();
+        BurnVisuals::new(color, move |scale| {
+            // scaling shouldn't change the number of edges,
+            // but fall back to the unscaled one just in case
+            let scaled = shape
+                .scaled(scale)
+                .generate_outline(origin_pose())
+                .into_iter()
+                .nth(piece);
+            sf::Mesh::from(scaled.map_or(coll, |(_, coll)| coll))
+        })
+    }
+
+    fn finish_collider(&self, coll: sf::Collider) -> sf::Collider {
+        let mut coll = coll.with_material(DEFAULT_PHYSICS_MATERIAL);
         if self.corner_radius > 0.0 {
             coll.shape = coll.shape.rounded_inward(self.corner_radius);
         }
         coll
     }
+
+    /// Insert the shape into the physics world as solid static geometry
+    /// (see `generate_outline`), or as a new dynamic body if a density is given
+    /// (see `generate_collider`), with a mesh and burn visuals for each collider.
+    pub fn insert_solid(
+        &self,
+        pose: sf::Pose,
+        density: Option<f64>,
+        color: [f32; 4],
+        physics: &mut sf::PhysicsWorld,
+    ) -> Vec<SolidPiece> {
+        match density {
+            Some(density) => {
+                let (pose, coll) = self.generate_collider(pose);
+                let body = sf::Body::new_dynamic(coll.info(), density);
+                let body_key = physics.entity_set.insert_body(body);
+                vec![SolidPiece {
+                    pose,
+                    body_key: Some(body_key),
+                    coll_key: physics.entity_set.attach_collider(body_key, coll),
+                    mesh: sf::Mesh::from(coll).with_color(color),
+                    visuals: self.burn_visuals(color),
+                }]
+            }
+            None => self
+                .generate_outline(pose)
+                .into_iter()
+                .enumerate()
+                .map(|(piece, (pose, coll))| SolidPiece {
+                    pose,
+                    body_key: None,
+                    coll_key: physics.entity_set.insert_collider(coll),
+                    mesh: sf::Mesh::from(coll).with_color(color),
+                    visuals: self.outline_burn_visuals(piece, coll, color),
+                })
+                .collect(),
+        }
+    }
+
+    /// Insert the shape as a static sensor that only detects the player.
+    pub fn insert_interactable(
+        &self,
+        pose: sf::Pose,
+        physics: &mut sf::PhysicsWorld,
+    ) -> (sf::Pose, sf::ColliderKey, sf::Collider) {
+        let (pose, coll) = self.generate_collider(pose);
+        let coll = coll.sensor().with_layer(collision_layers::INTERACTABLE);
+        (pose, physics.entity_set.insert_collider(coll), coll)
+    }
+}
+
+/// One collider of a solid object inserted with `TiledCollider::insert_solid`.
+pub struct SolidPiece {
+    pub pose: sf::Pose,
+    pub body_key: Option<sf::BodyKey>,
+    pub coll_key: sf::ColliderKey,
+    pub mesh: sf::Mesh,
+    /// Only needed if the object is flammable.
+    pub visuals: BurnVisuals,
+}
+
+/// Pose at the origin, for generating colliders relative to an object.
+fn origin_pose() -> sf::Pose {
+    sf::Pose::new(sf::Vec2::zero(), sf::Angle::Rad(0.0).into())
+}
+
+#[inline]
+fn default_checkpoint_radius() -> f64 {
+    1.0
+}
+
+#[inline]
+fn default_torch_heat() -> f64 {
+    100.0
+}
+
+#[inline]
+fn default_heat_radius() -> f64 {
+    0.5
+}
+
+#[inline]
+fn default_lens_heat() -> f64 {
+    50.0
+}
+
+#[inline]
+fn default_lens_range() -> f64 {
+    20.0
+}
+
+#[inline]
+fn default_wetting_rate() -> f64 {
+    0.5
+}
+
+#[inline]
+fn default_weed_material() -> String {
+    "dry_weed".into()
+}
+
+#[inline]
+fn default_flamevine_material() -> String {
+    "flamevine".into()
 }
 
 /// Defaults for bool fields that aren't present
@@ -0,0 +1,343 @@
+//! Cleaning up arbitrary polygons drawn in Tiled
+//! and decomposing them into convex pieces for checking them.
+
+use starframe as sf;
+
+/// Points closer than this are considered the same,
+/// and corners with less (doubled) area than this are considered straight.
+const EPSILON: f64 = 1e-9;
+
+/// Reasons a polygon can't be split into convex pieces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PolygonError {
+    /// Fewer than three corners left after removing repeated points and straight corners.
+    TooFewPoints(usize),
+    SelfIntersecting,
+}
+
+/// Split a simple polygon into convex pieces.
+///
+/// Repeated points and points in the middle of straight edges are removed first.
+/// The polygon is then triangulated by ear clipping,
+/// and adjacent triangles are merged back together as long as the result stays convex
+/// (Hertel-Mehlhorn). This isn't the minimal number of pieces,
+/// but it's at most four times that, which is plenty good for level geometry.
+///
+/// Works with either winding order. Output pieces are counterclockwise.
+pub fn convex_decomposition(points: &[sf::Vec2]) -> Result<Vec<Vec<sf::Vec2>>, PolygonError> {
+    let mut points = points.to_vec();
+    remove_redundant_points(&mut points);
+    if points.len() < 3 {
+        return Err(PolygonError::TooFewPoints(points.len()));
+    }
+    if is_self_intersecting(&points) {
+        return Err(PolygonError::SelfIntersecting);
+    }
+    if signed_area(&points) < 0.0 {
+        points.reverse();
+    }
+
+    let mut pieces = triangulate(&points).ok_or(PolygonError::SelfIntersecting)?;
+    merge_convex(&points, &mut pieces);
+
+    Ok(pieces
+        .into_iter()
+        .map(|piece| piece.into_iter().map(|i| points[i]).collect())
+        .collect())
+}
+
+/// Copy of a polygon with repeated points and points in the middle of straight edges removed.
+pub fn without_redundant_points(points: &[sf::Vec2]) -> Vec<sf::Vec2> {
+    let mut points = points.to_vec();
+    remove_redundant_points(&mut points);
+    points
+}
+
+/// Convex hull of a point set, counterclockwise (Andrew's monotone chain).
+pub fn convex_hull(points: &[sf::Vec2]) -> Vec<sf::Vec2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    let mut hull: Vec<sf::Vec2> = Vec::with_capacity(sorted.len() * 2);
+    // lower hull, then upper hull
+    for pass in [sorted.clone(), sorted.into_iter().rev().collect()] {
+        let start_len = hull.len();
+        for p in pass {
+            while hull.len() >= start_len + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // last point of each pass is the first point of the next
+        hull.pop();
+    }
+    hull
+}
+
+/// Remove repeated points (including a repeat of the first point at the end,
+/// which Tiled sometimes produces) and points in the middle of straight edges.
+/// These would otherwise turn into zero-area triangles that stop ear clipping.
+fn remove_redundant_points(points: &mut Vec<sf::Vec2>) {
+    while points.len() >= 3 {
+        let n = points.len();
+        let redundant = (0..n).find(|&i| {
+            let (prev, curr, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+            (curr - prev).mag_sq() < EPSILON || cross(prev, curr, next).abs() < EPSILON
+        });
+        let Some(i) = redundant else { return };
+        points.remove(i);
+    }
+    points.dedup_by(|a, b| (*a - *b).mag_sq() < EPSILON);
+}
+
+/// Check if any two non-adjacent edges of the polygon touch.
+fn is_self_intersecting(points: &[sf::Vec2]) -> bool {
+    let n = points.len();
+    let edge = |i: usize| (points[i], points[(i + 1) % n]);
+    (0..n).any(|i| {
+        // the last edge is adjacent to the first
+        let end = if i == 0 { n - 1 } else { n };
+        (i + 2..end).any(|j| {
+            let ((p1, p2), (q1, q2)) = (edge(i), edge(j));
+            segments_intersect(p1, p2, q1, q2)
+        })
+    })
+}
+
+/// Ear clipping triangulation of a counterclockwise polygon.
+/// Returns triangles as indices into `points`,
+/// or None if it gets stuck, which shouldn't happen with a simple polygon.
+fn triangulate(points: &[sf::Vec2]) -> Option<Vec<Vec<usize>>> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let prev = remaining[(i + n - 1) % n];
+            let curr = remaining[i];
+            let next = remaining[(i + 1) % n];
+            let (a, b, c) = (points[prev], points[curr], points[next]);
+            cross(a, b, c) > 0.0
+                && !remaining
+                    .iter()
+                    .filter(|&&j| j != prev && j != curr && j != next)
+                    .any(|&j| point_in_triangle(points[j], a, b, c))
+        });
+        let i = ear?;
+
+        triangles.push(vec![
+            remaining[(i + n - 1) % n],
+            remaining[i],
+            remaining[(i + 1) % n],
+        ]);
+        remaining.remove(i);
+    }
+    triangles.push(remaining);
+
+    Some(triangles)
+}
+
+/// Merge pieces sharing an edge as long as the result is convex.
+fn merge_convex(points: &[sf::Vec2], pieces: &mut Vec<Vec<usize>>) {
+    'search: loop {
+        for i in 0..pieces.len() {
+            for j in (i + 1)..pieces.len() {
+                let Some(merged) = try_merge(points, &pieces[i], &pieces[j]) else { continue };
+                pieces[i] = merged;
+                pieces.swap_remove(j);
+                continue 'search;
+            }
+        }
+        break;
+    }
+}
+
+/// Merge two counterclockwise pieces along a shared edge
+/// if they have one and the result is convex.
+fn try_merge(points: &[sf::Vec2], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    // a shared edge goes (x -> y) in `a` and (y -> x) in `b`
+    let (a_start, b_start) = (0..a.len()).find_map(|ai| {
+        let (x, y) = (a[ai], a[(ai + 1) % a.len()]);
+        (0..b.len())
+            .find(|&bi| b[bi] == y && b[(bi + 1) % b.len()] == x)
+            .map(|bi| ((ai + 1) % a.len(), (bi + 1) % b.len()))
+    })?;
+
+    // walk `a` from y around to x, then `b` from after x around to before y
+    let mut merged: Vec<usize> = (0..a.len()).map(|k| a[(a_start + k) % a.len()]).collect();
+    merged.extend((1..b.len() - 1).map(|k| b[(b_start + k) % b.len()]));
+
+    let n = merged.len();
+    let is_convex = (0..n).all(|k| {
+        cross(
+            points[merged[k]],
+            points[merged[(k + 1) % n]],
+            points[merged[(k + 2) % n]],
+        ) >= 0.0
+    });
+    is_convex.then_some(merged)
+}
+
+//
+// geometry utilities
+//
+
+/// Twice the signed area of the triangle abc, positive if counterclockwise.
+#[inline]
+fn cross(a: sf::Vec2, b: sf::Vec2, c: sf::Vec2) -> f64 {
+    let (ab, ac) = (b - a, c - a);
+    ab.x * ac.y - ab.y * ac.x
+}
+
+fn signed_area(points: &[sf::Vec2]) -> f64 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (p, q) = (points[i], points[(i + 1) % n]);
+            p.x * q.y - q.x * p.y
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Check if the segments p1-p2 and q1-q2 have any points in common.
+fn segments_intersect(p1: sf::Vec2, p2: sf::Vec2, q1: sf::Vec2, q2: sf::Vec2) -> bool {
+    let (d1, d2) = (cross(q1, q2, p1), cross(q1, q2, p2));
+    let (d3, d4) = (cross(p1, p2, q1), cross(p1, p2, q2));
+    if d1.abs() < EPSILON && d2.abs() < EPSILON {
+        // on the same line, check if the projections overlap
+        let dir = p2 - p1;
+        let (t1, t2) = ((q1 - p1).dot(dir), (q2 - p1).dot(dir));
+        return t1.max(t2) >= 0.0 && t1.min(t2) <= dir.mag_sq();
+    }
+    d1 * d2 <= 0.0 && d3 * d4 <= 0.0
+}
+
+fn point_in_triangle(p: sf::Vec2, a: sf::Vec2, b: sf::Vec2, c: sf::Vec2) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coords: &[(f64, f64)]) -> Vec<sf::Vec2> {
+        coords.iter().map(|&(x, y)| sf::Vec2::new(x, y)).collect()
+    }
+
+    fn assert_convex_ccw(piece: &[sf::Vec2]) {
+        let n = piece.len();
+        assert!(n >= 3, "piece has only {n} points");
+        for k in 0..n {
+            let turn = cross(piece[k], piece[(k + 1) % n], piece[(k + 2) % n]);
+            assert!(
+                turn >= 0.0,
+                "piece {piece:?} isn't convex and counterclockwise"
+            );
+        }
+    }
+
+    fn total_area(pieces: &[Vec<sf::Vec2>]) -> f64 {
+        pieces.iter().map(|piece| signed_area(piece)).sum()
+    }
+
+    #[test]
+    fn convex_polygon_stays_in_one_piece() {
+        let square = points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let pieces = convex_decomposition(&square).unwrap();
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].len(), 4);
+        assert_convex_ccw(&pieces[0]);
+    }
+
+    #[test]
+    fn concave_polygon_is_split() {
+        // L shape, clockwise to check that winding is fixed
+        let l_shape = points(&[
+            (0.0, 0.0),
+            (0.0, 2.0),
+            (1.0, 2.0),
+            (1.0, 1.0),
+            (2.0, 1.0),
+            (2.0, 0.0),
+        ]);
+        let pieces = convex_decomposition(&l_shape).unwrap();
+        assert_eq!(pieces.len(), 2);
+        for piece in &pieces {
+            assert_convex_ccw(piece);
+        }
+        assert!((total_area(&pieces) - 3.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn redundant_points_are_removed() {
+        // square with a repeated closing point, a duplicate and points along its edges
+        let square = points(&[
+            (0.0, 0.0),
+            (0.5, 0.0),
+            (1.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+            (0.0, 0.5),
+            (0.0, 0.0),
+        ]);
+        let pieces = convex_decomposition(&square).unwrap();
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].len(), 4);
+        assert!((total_area(&pieces) - 1.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn invalid_polygons_are_errors() {
+        let line = points(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]);
+        assert_eq!(
+            convex_decomposition(&line),
+            Err(PolygonError::TooFewPoints(2))
+        );
+        let bowtie = points(&[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]);
+        assert_eq!(
+            convex_decomposition(&bowtie),
+            Err(PolygonError::SelfIntersecting)
+        );
+    }
+
+    #[test]
+    fn collinear_edges_that_dont_touch_are_fine() {
+        // U shape with both top edges on the same line
+        let u_shape = points(&[
+            (0.0, 0.0),
+            (3.0, 0.0),
+            (3.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        let pieces = convex_decomposition(&u_shape).unwrap();
+        assert!((total_area(&pieces) - 5.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn convex_hull_of_concave_polygon() {
+        let l_shape = points(&[
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ]);
+        let hull = convex_hull(&l_shape);
+        assert_eq!(hull.len(), 5);
+        assert_convex_ccw(&hull);
+    }
+}
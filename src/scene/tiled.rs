@@ -0,0 +1,759 @@
+//! Loader for scenes saved in Tiled's JSON map format (`.tmj`).
+//!
+//! Tiled's coordinate system doesn't match Starframe's, so we transform
+//! everything on load: Y axis up, angles to counterclockwise radians,
+//! object origins moved to their centers, and a scaling factor
+//! so we can use Tiled at a comfortable zoom level.
+//! Custom properties on objects and the map itself are flattened
+//! into first-class fields before deserializing into a `Scene`.
+//!
+//! Tile layers are turned into static level geometry.
+//! Tiles are solid if they (or their tileset) have the custom property `solid`,
+//! and adjacent solid tiles are merged into as few rectangles as possible.
+//! Tilesets must be embedded in the map, external tileset files aren't supported.
+
+use std::{borrow::Cow, collections::HashSet};
+
+use assets_manager::{loader, BoxedError};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use super::{
+    validate::{IssueKind, ObjectRef, SceneError, SceneIssue},
+    Recipe, Scene,
+};
+
+/// Tiled pixels per Starframe unit.
+const TILED_SCALE: f64 = 30.0;
+
+pub struct TiledLoader;
+impl loader::Loader<Scene> for TiledLoader {
+    fn load(content: Cow<[u8]>, _ext: &str) -> Result<Scene, BoxedError> {
+        Ok(parse_scene(&content)?)
+    }
+}
+
+/// Parse a Tiled map into a scene,
+/// collecting every problem that prevents it from loading into the error.
+pub fn parse_scene(content: &[u8]) -> Result<Scene, SceneError> {
+    let map: TiledMap = serde_json::from_slice(content).map_err(|err| SceneError {
+        issues: vec![SceneIssue::error(
+            None,
+            IssueKind::InvalidMap(err.to_string()),
+        )],
+    })?;
+    map.to_scene()
+}
+
+//
+// raw Tiled data
+//
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct TiledMap {
+    tilewidth: f64,
+    tileheight: f64,
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    tilesets: Vec<TiledTileset>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+// every layer type can be offset, and offsets of group layers apply to everything inside them
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum TiledLayer {
+    ObjectGroup {
+        name: String,
+        // parsed one by one later so errors can be reported with the object's id
+        objects: Vec<Value>,
+        #[serde(default)]
+        offsetx: f64,
+        #[serde(default)]
+        offsety: f64,
+    },
+    TileLayer {
+        name: String,
+        // finite maps have `data` directly on the layer,
+        // infinite maps split it into chunks
+        #[serde(default)]
+        data: Option<TiledTileData>,
+        width: i32,
+        #[serde(default)]
+        chunks: Vec<TiledChunk>,
+        // csv if not set, otherwise `data` is a base64 string we don't decode
+        #[serde(default)]
+        encoding: Option<String>,
+        #[serde(default)]
+        compression: Option<String>,
+        #[serde(default)]
+        offsetx: f64,
+        #[serde(default)]
+        offsety: f64,
+    },
+    Group {
+        layers: Vec<TiledLayer>,
+        #[serde(default)]
+        offsetx: f64,
+        #[serde(default)]
+        offsety: f64,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct TiledChunk {
+    data: TiledTileData,
+    x: i32,
+    y: i32,
+    width: i32,
+}
+
+/// Tile ids, either as a plain array (CSV layer format)
+/// or a base64 string (Base64 layer formats, possibly compressed).
+#[derive(Clone, Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum TiledTileData {
+    Csv(Vec<u32>),
+    Encoded(String),
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct TiledTileset {
+    firstgid: u32,
+    // only present for external tilesets, which we don't support
+    #[serde(default)]
+    source: Option<String>,
+    #[serde(default)]
+    tiles: Vec<TiledTile>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct TiledTile {
+    id: u32,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct TiledObject {
+    #[serde(default)]
+    id: u32,
+    // Tiled 1.9 renamed `type` to `class`, 1.10 renamed it back
+    #[serde(rename = "type", alias = "class", default)]
+    class: String,
+    x: f64,
+    y: f64,
+    // left out of the recipe if missing so that recipes with a shape report it
+    #[serde(default)]
+    width: Option<f64>,
+    #[serde(default)]
+    height: Option<f64>,
+    #[serde(default)]
+    rotation: f64,
+    #[serde(default)]
+    polyline: Option<Vec<TiledPoint>>,
+    #[serde(default)]
+    polygon: Option<Vec<TiledPoint>>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Clone, Copy, Debug, serde::Deserialize)]
+struct TiledPoint {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Clone, Debug, serde::Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: Value,
+}
+
+//
+// transformation into the format `Scene` deserializes from
+//
+
+impl TiledMap {
+    fn to_scene(&self) -> Result<Scene, SceneError> {
+        let mut issues = Vec::new();
+
+        if let Some(source) = self.tilesets.iter().find_map(|ts| ts.source.as_ref()) {
+            issues.push(SceneIssue::error(
+                None,
+                IssueKind::InvalidMap(format!(
+                    "external tileset {source} not supported, embed it in the map"
+                )),
+            ));
+        }
+
+        let mut props = Map::new();
+        flatten_properties(&self.properties, &mut props);
+        let mut scene: Scene = serde_json::from_value(Value::Object(props)).unwrap_or_else(|err| {
+            issues.push(SceneIssue::error(
+                None,
+                IssueKind::InvalidMap(err.to_string()),
+            ));
+            Scene::default()
+        });
+
+        let mut recipes = Vec::new();
+        for layer in &self.layers {
+            self.collect_recipes(layer, (0.0, 0.0), &mut recipes, &mut issues);
+        }
+        for (source, recipe) in recipes {
+            match Recipe::deserialize(&recipe) {
+                Ok(recipe) => {
+                    scene.recipes.push(recipe);
+                    scene.sources.push(source);
+                }
+                Err(err) => issues.push(SceneIssue::error(
+                    Some(source),
+                    IssueKind::InvalidObject(err.to_string()),
+                )),
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(scene)
+        } else {
+            Err(SceneError { issues })
+        }
+    }
+
+    /// Turn a layer into recipes, moving everything by `parent_offset`
+    /// (the summed offsets of the groups the layer is in) plus the layer's own offset.
+    fn collect_recipes(
+        &self,
+        layer: &TiledLayer,
+        parent_offset: (f64, f64),
+        recipes: &mut Vec<(ObjectRef, Value)>,
+        issues: &mut Vec<SceneIssue>,
+    ) {
+        match layer {
+            TiledLayer::ObjectGroup {
+                name,
+                objects,
+                offsetx,
+                offsety,
+            } => {
+                for obj in objects {
+                    let source = ObjectRef {
+                        id: obj["id"].as_u64().unwrap_or(0) as u32,
+                        name: obj["name"].as_str().unwrap_or_default().to_string(),
+                        layer: name.clone(),
+                    };
+                    match TiledObject::deserialize(obj) {
+                        Ok(mut obj) => {
+                            obj.x += parent_offset.0 + offsetx;
+                            obj.y += parent_offset.1 + offsety;
+                            recipes.push((source, obj.to_recipe_value()));
+                        }
+                        Err(err) => issues.push(SceneIssue::error(
+                            Some(source),
+                            IssueKind::InvalidObject(err.to_string()),
+                        )),
+                    }
+                }
+            }
+            TiledLayer::TileLayer {
+                name,
+                data,
+                width,
+                chunks,
+                encoding,
+                compression,
+                offsetx,
+                offsety,
+            } => {
+                let layer_error = |msg: String| {
+                    SceneIssue::error(
+                        None,
+                        IssueKind::InvalidMap(format!("tile layer \"{name}\": {msg}")),
+                    )
+                };
+                if let Some(encoding) = encoding.as_deref().filter(|&e| e != "csv") {
+                    let compression = compression
+                        .as_deref()
+                        .filter(|c| !c.is_empty())
+                        .map(|c| format!(" with {c} compression"))
+                        .unwrap_or_default();
+                    issues.push(layer_error(format!(
+                        "{encoding} encoding{compression} not supported, set the layer format to CSV"
+                    )));
+                    return;
+                }
+
+                let mut solid_tiles: HashSet<(i32, i32)> = HashSet::new();
+                let mut add_tiles = |data: &TiledTileData, origin: (i32, i32), width: i32| {
+                    let TiledTileData::Csv(data) = data else {
+                        issues.push(layer_error("unexpected encoded tile data".into()));
+                        return;
+                    };
+                    if width <= 0 {
+                        if !data.is_empty() {
+                            issues.push(layer_error(format!("invalid width {width}")));
+                        }
+                        return;
+                    }
+                    for (idx, &gid) in data.iter().enumerate() {
+                        if self.is_solid(gid) {
+                            let idx = idx as i32;
+                            solid_tiles.insert((origin.0 + idx % width, origin.1 + idx / width));
+                        }
+                    }
+                };
+                if let Some(data) = data {
+                    add_tiles(data, (0, 0), *width);
+                }
+                for chunk in chunks {
+                    add_tiles(&chunk.data, (chunk.x, chunk.y), chunk.width);
+                }
+
+                let (offsetx, offsety) = (parent_offset.0 + offsetx, parent_offset.1 + offsety);
+                for rect in merge_tiles(solid_tiles) {
+                    // pretend the merged rectangle is a Tiled object
+                    // so it goes through the same transformation
+                    let obj = TiledObject {
+                        id: 0,
+                        class: "StaticCollider".into(),
+                        x: offsetx + rect.x as f64 * self.tilewidth,
+                        y: offsety + rect.y as f64 * self.tileheight,
+                        width: Some(rect.width as f64 * self.tilewidth),
+                        height: Some(rect.height as f64 * self.tileheight),
+                        rotation: 0.0,
+                        polyline: None,
+                        polygon: None,
+                        properties: Vec::new(),
+                    };
+                    let source = ObjectRef {
+                        id: 0,
+                        name: format!("solid tiles at ({}, {})", rect.x, rect.y),
+                        layer: name.clone(),
+                    };
+                    recipes.push((source, obj.to_recipe_value()));
+                }
+            }
+            TiledLayer::Group {
+                layers,
+                offsetx,
+                offsety,
+            } => {
+                let offset = (parent_offset.0 + offsetx, parent_offset.1 + offsety);
+                for layer in layers {
+                    self.collect_recipes(layer, offset, recipes, issues);
+                }
+            }
+            TiledLayer::Other => {}
+        }
+    }
+
+    /// Check if the tile with the given global id has the `solid` property set,
+    /// either on the tile itself or as a default on its tileset.
+    fn is_solid(&self, gid: u32) -> bool {
+        let gid = gid & !TILE_FLIP_FLAGS;
+        if gid == 0 {
+            return false;
+        }
+        // tilesets are sorted by firstgid, the tile belongs to the last one that starts before it
+        let Some(tileset) = self.tilesets.iter().rev().find(|ts| ts.firstgid <= gid) else { return false };
+        let local_id = gid - tileset.firstgid;
+
+        let tile_prop = tileset
+            .tiles
+            .iter()
+            .find(|t| t.id == local_id)
+            .and_then(|t| find_bool_property(&t.properties, "solid"));
+        tile_prop
+            .or_else(|| find_bool_property(&tileset.properties, "solid"))
+            .unwrap_or(false)
+    }
+}
+
+/// The highest bits of a tile's global id are used for flipping and rotation flags.
+const TILE_FLIP_FLAGS: u32 = 0xf000_0000;
+
+/// A rectangle of tiles, in tile coordinates.
+#[derive(Clone, Copy, Debug)]
+struct TileRect {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+/// Greedily merge a set of tiles into rectangles,
+/// extending each one as far right and then as far down as possible.
+fn merge_tiles(mut tiles: HashSet<(i32, i32)>) -> Vec<TileRect> {
+    let mut sorted: Vec<(i32, i32)> = tiles.iter().copied().collect();
+    // row-major order so rectangles grow from their top left corners
+    sorted.sort_unstable_by_key(|&(x, y)| (y, x));
+
+    let mut rects = Vec::new();
+    for (x, y) in sorted {
+        if !tiles.contains(&(x, y)) {
+            // already part of a rectangle
+            continue;
+        }
+
+        let mut width = 1;
+        while tiles.contains(&(x + width, y)) {
+            width += 1;
+        }
+        let mut height = 1;
+        while (x..x + width).all(|col| tiles.contains(&(col, y + height))) {
+            height += 1;
+        }
+
+        for row in y..y + height {
+            for col in x..x + width {
+                tiles.remove(&(col, row));
+            }
+        }
+        rects.push(TileRect {
+            x,
+            y,
+            width,
+            height,
+        });
+    }
+
+    rects
+}
+
+impl TiledObject {
+    fn to_recipe_value(&self) -> Value {
+        // Tiled rotates clockwise around the top left corner of the object
+        let angle = self.rotation.to_radians();
+        let (sin, cos) = angle.sin_cos();
+        let hw = self.width.unwrap_or(0.0) / 2.0;
+        let hh = self.height.unwrap_or(0.0) / 2.0;
+        let x_center = self.x + hw * cos - hh * sin;
+        let y_center = -(self.y + hw * sin + hh * cos);
+
+        let mut recipe = Map::new();
+        recipe.insert("type".into(), json!(self.class));
+        recipe.insert("id".into(), json!(self.id));
+        recipe.insert(
+            "pose".into(),
+            json!({
+                "x": scale(x_center),
+                "y": scale(y_center),
+                "rotation": std::f64::consts::TAU - angle,
+            }),
+        );
+        if let Some(width) = self.width {
+            recipe.insert("width".into(), json!(scale(width)));
+        }
+        if let Some(height) = self.height {
+            recipe.insert("height".into(), json!(scale(height)));
+        }
+        // polyline and polygon points are relative to the object's origin
+        // and don't need to be moved since it has no width or height
+        let scale_points = |points: &[TiledPoint]| -> Value {
+            points
+                .iter()
+                .map(|p| json!({ "x": scale(p.x), "y": scale(-p.y) }))
+                .collect()
+        };
+        if let Some(polyline) = &self.polyline {
+            recipe.insert("polyline".into(), scale_points(polyline));
+        }
+        if let Some(polygon) = &self.polygon {
+            recipe.insert("polygon".into(), scale_points(polygon));
+        }
+        flatten_properties(&self.properties, &mut recipe);
+
+        Value::Object(recipe)
+    }
+}
+
+fn find_bool_property(props: &[TiledProperty], name: &str) -> Option<bool> {
+    props
+        .iter()
+        .find(|p| p.name == name)
+        .and_then(|p| p.value.as_bool())
+}
+
+/// Custom properties from `{ name, value }` objects into first-class fields.
+fn flatten_properties(props: &[TiledProperty], target: &mut Map<String, Value>) {
+    for prop in props {
+        target.insert(prop.name.clone(), prop.value.clone());
+    }
+}
+
+#[inline]
+fn scale(val: f64) -> f64 {
+    val / TILED_SCALE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn merged(tiles: &[(i32, i32)]) -> Vec<(i32, i32, i32, i32)> {
+        let mut rects: Vec<_> = merge_tiles(tiles.iter().copied().collect())
+            .into_iter()
+            .map(|r| (r.x, r.y, r.width, r.height))
+            .collect();
+        rects.sort_unstable();
+        rects
+    }
+
+    fn tile_map(layer: Value) -> Vec<u8> {
+        json!({
+            "tilewidth": 30.0,
+            "tileheight": 30.0,
+            "layers": [layer],
+            "tilesets": [{
+                "firstgid": 1,
+                "properties": [{ "name": "solid", "value": true }],
+            }],
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn object_map(objects: Value, properties: Value) -> Vec<u8> {
+        json!({
+            "tilewidth": 30.0,
+            "tileheight": 30.0,
+            "layers": [{ "type": "objectgroup", "name": "objects", "objects": objects }],
+            "properties": properties,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{a} != {b}");
+    }
+
+    #[test]
+    fn objects_are_centered_and_scaled() {
+        let map = object_map(
+            json!([{
+                "id": 1,
+                "type": "StaticCollider",
+                "x": 30.0,
+                "y": 60.0,
+                "width": 60.0,
+                "height": 30.0,
+            }]),
+            json!([]),
+        );
+        let scene = parse_scene(&map).unwrap();
+        let Recipe::StaticCollider { pose, collider, .. } = &scene.recipes[0] else {
+            panic!("expected a StaticCollider, got {:?}", scene.recipes[0]);
+        };
+        assert_close(pose.0.translation.x, 2.0);
+        assert_close(pose.0.translation.y, -2.5);
+        assert_close(collider.width, 2.0);
+        assert_close(collider.height, 1.0);
+    }
+
+    #[test]
+    fn rotation_is_around_the_top_left_corner() {
+        let map = object_map(
+            json!([{
+                "id": 1,
+                "type": "StaticCollider",
+                "x": 0.0,
+                "y": 0.0,
+                "width": 60.0,
+                "height": 30.0,
+                "rotation": 90.0,
+            }]),
+            json!([]),
+        );
+        let scene = parse_scene(&map).unwrap();
+        let pose = scene.recipes[0].pose().0;
+        assert_close(pose.translation.x, -0.5);
+        assert_close(pose.translation.y, -1.0);
+        // 90 degrees clockwise is -90 counterclockwise
+        let dir = pose.rotation * starframe::Vec2::unit_x();
+        assert_close(dir.x, 0.0);
+        assert_close(dir.y, -1.0);
+    }
+
+    #[test]
+    fn properties_are_flattened() {
+        let map = object_map(
+            json!([{
+                "id": 3,
+                "type": "PhysicsObject",
+                "x": 0.0,
+                "y": 0.0,
+                "width": 30.0,
+                "height": 30.0,
+                "properties": [{ "name": "material", "type": "string", "value": "wood" }],
+            }]),
+            json!([{ "name": "time_limit", "type": "float", "value": 60.0 }]),
+        );
+        let scene = parse_scene(&map).unwrap();
+        assert_eq!(scene.time_limit, Some(60.0));
+        assert_eq!(scene.recipes[0].material(), Some("wood"));
+        assert_eq!(scene.sources[0].id, 3);
+        assert_eq!(scene.sources[0].layer, "objects");
+    }
+
+    #[test]
+    fn every_invalid_object_is_reported() {
+        let map = object_map(
+            json!([
+                { "id": 1, "type": "NotARecipe", "x": 0.0, "y": 0.0 },
+                { "id": 2, "type": "StaticCollider", "x": 0.0 },
+            ]),
+            json!([]),
+        );
+        let err = parse_scene(&map).unwrap_err();
+        let mut ids: Vec<u32> = err
+            .issues
+            .iter()
+            .map(|issue| issue.object.as_ref().unwrap().id)
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn missing_size_is_reported_for_shapes() {
+        let map = object_map(
+            json!([
+                { "id": 1, "type": "StaticCollider", "x": 0.0, "y": 0.0, "width": 30.0 },
+                { "id": 2, "type": "Torch", "x": 0.0, "y": 0.0 },
+            ]),
+            json!([]),
+        );
+        let err = parse_scene(&map).unwrap_err();
+        assert_eq!(err.issues.len(), 1);
+        assert_eq!(err.issues[0].object.as_ref().unwrap().id, 1);
+        let msg = err.issues[0].to_string();
+        assert!(msg.contains("height"), "{msg}");
+    }
+
+    #[test]
+    fn merge_tiles_fills_a_block_with_one_rect() {
+        let block: Vec<_> = (0..3).flat_map(|x| (0..2).map(move |y| (x, y))).collect();
+        assert_eq!(merged(&block), vec![(0, 0, 3, 2)]);
+    }
+
+    #[test]
+    fn merge_tiles_extends_right_before_down() {
+        // ##
+        // #
+        assert_eq!(
+            merged(&[(0, 0), (1, 0), (0, 1)]),
+            vec![(0, 0, 2, 1), (0, 1, 1, 1)]
+        );
+        // #
+        // ##
+        assert_eq!(
+            merged(&[(0, 0), (0, 1), (1, 1)]),
+            vec![(0, 0, 1, 1), (0, 1, 2, 1)]
+        );
+    }
+
+    #[test]
+    fn merge_tiles_keeps_separate_groups_apart() {
+        assert_eq!(
+            merged(&[(0, 0), (2, 0), (-5, 3), (-4, 3)]),
+            vec![(-5, 3, 2, 1), (0, 0, 1, 1), (2, 0, 1, 1)]
+        );
+        assert!(merged(&[]).is_empty());
+    }
+
+    #[test]
+    fn solid_tiles_become_static_colliders() {
+        let map = tile_map(json!({
+            "type": "tilelayer",
+            "name": "tiles",
+            "width": 3,
+            "data": [1, 1, 0, 1, 1, 0],
+        }));
+        let scene = parse_scene(&map).unwrap();
+        assert_eq!(scene.recipes.len(), 1);
+        let Recipe::StaticCollider { pose, collider, .. } = &scene.recipes[0] else {
+            panic!("expected a StaticCollider, got {:?}", scene.recipes[0]);
+        };
+        assert_eq!((collider.width, collider.height), (2.0, 2.0));
+        assert_eq!(pose.0.translation, starframe::Vec2::new(1.0, -1.0));
+    }
+
+    #[test]
+    fn layer_offsets_add_up_through_groups() {
+        let map = tile_map(json!({
+            "type": "group",
+            "offsetx": 30.0,
+            "offsety": -60.0,
+            "layers": [
+                {
+                    "type": "objectgroup",
+                    "name": "objects",
+                    "offsetx": 30.0,
+                    "objects": [{
+                        "id": 1,
+                        "type": "StaticCollider",
+                        "x": 0.0,
+                        "y": 0.0,
+                        "width": 30.0,
+                        "height": 30.0,
+                    }],
+                },
+                {
+                    "type": "tilelayer",
+                    "name": "tiles",
+                    "width": 1,
+                    "data": [1],
+                },
+            ],
+        }));
+        let scene = parse_scene(&map).unwrap();
+        assert_eq!(scene.recipes.len(), 2);
+        let object_pos = scene.recipes[0].pose().0.translation;
+        assert_close(object_pos.x, 2.5);
+        assert_close(object_pos.y, 1.5);
+        let tile_pos = scene.recipes[1].pose().0.translation;
+        assert_close(tile_pos.x, 1.5);
+        assert_close(tile_pos.y, 1.5);
+    }
+
+    #[test]
+    fn tile_layer_without_width_is_an_error() {
+        let map = tile_map(json!({
+            "type": "tilelayer",
+            "name": "tiles",
+            "data": [1, 1],
+        }));
+        assert!(parse_scene(&map).is_err());
+
+        let map = tile_map(json!({
+            "type": "tilelayer",
+            "name": "tiles",
+            "width": 0,
+            "data": [1, 1],
+        }));
+        assert!(parse_scene(&map).is_err());
+    }
+
+    #[test]
+    fn encoded_tile_layer_is_reported() {
+        let map = tile_map(json!({
+            "type": "tilelayer",
+            "name": "tiles",
+            "width": 2,
+            "encoding": "base64",
+            "compression": "zlib",
+            "data": "eJxjZGBgAAAADAAD",
+        }));
+        let err = parse_scene(&map).unwrap_err();
+        assert_eq!(err.issues.len(), 1);
+        let msg = err.issues[0].to_string();
+        assert!(msg.contains("base64") && msg.contains("zlib"), "{msg}");
+    }
+}
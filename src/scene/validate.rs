@@ -0,0 +1,795 @@
+//! Checking scenes for problems and reporting them
+//! with enough context to find them in Tiled.
+
+use std::fmt;
+
+use starframe as sf;
+
+use super::{
+    polygon::{self, PolygonError},
+    Recipe, Scene, TiledCollider, TiledColliderShape,
+};
+use crate::{fire::FireMaterials, goals::GoalKind, ASSETS};
+
+/// Segments shorter than this in a capsule chain are considered zero-length.
+const MIN_SEGMENT_LENGTH: f64 = 0.001;
+/// Static colliders may overlap by this much without a warning,
+/// so that walls can be placed flush against floors.
+const OVERLAP_TOLERANCE: f64 = 0.05;
+
+/// The Tiled object a recipe came from.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectRef {
+    /// Tiled object id. Zero for things that aren't objects, e.g. merged tiles.
+    pub id: u32,
+    pub name: String,
+    pub layer: String,
+}
+
+impl fmt::Display for ObjectRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "object {}", self.id)?;
+        if !self.name.is_empty() {
+            write!(f, " \"{}\"", self.name)?;
+        }
+        write!(f, " on layer \"{}\"", self.layer)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    /// The scene can't be loaded.
+    Error,
+    /// The scene loads, but probably doesn't work as intended.
+    Warning,
+}
+
+#[derive(Clone, Debug)]
+pub enum IssueKind {
+    /// The map file itself couldn't be parsed.
+    InvalidMap(String),
+    /// An object couldn't be turned into a recipe,
+    /// e.g. because of an unknown type or a missing field.
+    InvalidObject(String),
+    NoSpawnPoint,
+    /// Multiple spawn points share the lowest checkpoint order,
+    /// so it's unclear which one the level starts from.
+    AmbiguousStartPoint(usize),
+    /// A polyline or polygon with too few points to make a shape.
+    DegenerateShape {
+        points: usize,
+    },
+    /// A polygon whose edges cross, which can't be split into convex pieces.
+    SelfIntersectingPolygon,
+    /// A segment of a capsule chain with (almost) no length.
+    ZeroLengthSegment {
+        index: usize,
+    },
+    OverlappingStaticGeometry {
+        other: ObjectRef,
+    },
+    /// A fire material that isn't in `fire_materials.json`.
+    /// The object falls back to the default material.
+    UnknownMaterial(String),
+    /// A reference to an object id that isn't in the scene.
+    UnknownTarget(u32),
+    /// The goal is to burn all targets, but there aren't any,
+    /// so the level would be completed as soon as it starts.
+    NoBurnTargets,
+    /// `max_vines` or `max_vine_length` is zero or negative,
+    /// so the player can't create any vines.
+    EmptyVineBudget {
+        property: &'static str,
+    },
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMap(err) => write!(f, "invalid map: {err}"),
+            Self::InvalidObject(err) => write!(f, "invalid object: {err}"),
+            Self::NoSpawnPoint => write!(f, "no PlayerSpawnPoint in scene"),
+            Self::AmbiguousStartPoint(count) => write!(
+                f,
+                "{count} PlayerSpawnPoints share the lowest checkpoint_order, expected one"
+            ),
+            Self::DegenerateShape { points } => write!(f, "shape has only {points} points"),
+            Self::SelfIntersectingPolygon => {
+                write!(f, "polygon intersects itself, using its convex hull")
+            }
+            Self::ZeroLengthSegment { index } => write!(f, "segment {index} has zero length"),
+            Self::OverlappingStaticGeometry { other } => {
+                write!(f, "overlaps static geometry from {other}")
+            }
+            Self::UnknownMaterial(name) => {
+                write!(f, "unknown fire material \"{name}\"")
+            }
+            Self::UnknownTarget(id) => write!(f, "target object {id} not found"),
+            Self::NoBurnTargets => {
+                write!(f, "goal is BurnAllTargets but nothing can be burned")
+            }
+            Self::EmptyVineBudget { property } => {
+                write!(f, "{property} doesn't allow any vines")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SceneIssue {
+    pub severity: Severity,
+    /// None if the issue is about the scene as a whole.
+    pub object: Option<ObjectRef>,
+    pub kind: IssueKind,
+}
+
+impl SceneIssue {
+    pub fn error(object: Option<ObjectRef>, kind: IssueKind) -> Self {
+        Self {
+            severity: Severity::Error,
+            object,
+            kind,
+        }
+    }
+
+    pub fn warning(object: Option<ObjectRef>, kind: IssueKind) -> Self {
+        Self {
+            severity: Severity::Warning,
+            object,
+            kind,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for SceneIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Error => write!(f, "error")?,
+            Severity::Warning => write!(f, "warning")?,
+        }
+        if let Some(object) = &self.object {
+            write!(f, " in {object}")?;
+        }
+        write!(f, ": {}", self.kind)
+    }
+}
+
+/// Error returned when a scene fails to load, containing every problem found.
+#[derive(Clone, Debug)]
+pub struct SceneError {
+    pub issues: Vec<SceneIssue>,
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "scene has {} problem(s)", self.issues.len())?;
+        for issue in &self.issues {
+            write!(f, "\n  {issue}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+//
+// checks on loaded scenes
+//
+
+impl Scene {
+    /// Check a successfully loaded scene for mistakes.
+    /// Problems that prevent loading altogether are reported
+    /// as a `SceneError` by the loader instead.
+    /// Errors found here don't stop the scene from loading, but it won't play as designed.
+    pub fn validate(&self) -> Vec<SceneIssue> {
+        let materials = ASSETS.load::<FireMaterials>("fire_materials").ok();
+        self.validate_with_materials(materials.map(|m| m.read()).as_deref())
+    }
+
+    /// `validate` with the given fire materials instead of the ones in the asset cache.
+    /// Material names aren't checked if there are none.
+    fn validate_with_materials(&self, materials: Option<&FireMaterials>) -> Vec<SceneIssue> {
+        let mut issues = Vec::new();
+
+        let spawn_orders: Vec<i32> = self
+            .recipes
+            .iter()
+            .filter_map(|r| match r {
+                Recipe::PlayerSpawnPoint {
+                    checkpoint_order, ..
+                } => Some(*checkpoint_order),
+                _ => None,
+            })
+            .collect();
+        match spawn_orders.iter().min() {
+            None => issues.push(SceneIssue::warning(None, IssueKind::NoSpawnPoint)),
+            Some(first) => {
+                let start_count = spawn_orders.iter().filter(|o| *o == first).count();
+                if start_count > 1 {
+                    issues.push(SceneIssue::warning(
+                        None,
+                        IssueKind::AmbiguousStartPoint(start_count),
+                    ));
+                }
+            }
+        }
+
+        if self.goal == GoalKind::BurnAllTargets
+            && !self.recipes.iter().any(|r| {
+                matches!(
+                    r,
+                    Recipe::Weed { .. }
+                        | Recipe::StaticCollider {
+                            burn_target: true,
+                            ..
+                        }
+                        | Recipe::PhysicsObject {
+                            burn_target: true,
+                            ..
+                        }
+                )
+            })
+        {
+            issues.push(SceneIssue::error(None, IssueKind::NoBurnTargets));
+        }
+
+        if self.max_vines == Some(0) {
+            issues.push(SceneIssue::warning(
+                None,
+                IssueKind::EmptyVineBudget {
+                    property: "max_vines",
+                },
+            ));
+        }
+        if self.max_vine_length.is_some_and(|length| length <= 0.0) {
+            issues.push(SceneIssue::warning(
+                None,
+                IssueKind::EmptyVineBudget {
+                    property: "max_vine_length",
+                },
+            ));
+        }
+
+        let mut static_shapes: Vec<(StaticShape, &ObjectRef)> = Vec::new();
+        for (recipe, source) in self.recipes_with_sources() {
+            if let (Some(materials), Some(material)) = (materials, recipe.material()) {
+                // the object would still spawn with default fire behavior,
+                // but that's almost certainly not what the level needs
+                if materials.get(material).is_none() {
+                    issues.push(SceneIssue::error(
+                        Some(source.clone()),
+                        IssueKind::UnknownMaterial(material.to_string()),
+                    ));
+                }
+            }
+
+            if let Some(kind) = recipe.collider().and_then(check_polygon) {
+                issues.push(SceneIssue::warning(Some(source.clone()), kind));
+            }
+
+            match recipe {
+                Recipe::StaticCapsuleChain {
+                    pose,
+                    polyline,
+                    thickness,
+                } => {
+                    if polyline.len() < 2 {
+                        issues.push(SceneIssue::warning(
+                            Some(source.clone()),
+                            IssueKind::DegenerateShape {
+                                points: polyline.len(),
+                            },
+                        ));
+                    }
+                    for (index, seg) in polyline.windows(2).enumerate() {
+                        if (seg[1] - seg[0]).mag() < MIN_SEGMENT_LENGTH {
+                            issues.push(SceneIssue::warning(
+                                Some(source.clone()),
+                                IssueKind::ZeroLengthSegment { index },
+                            ));
+                        }
+                    }
+                    let shape = StaticShape::of_capsule_chain(pose.0, polyline, *thickness);
+                    static_shapes.push((shape, source));
+                }
+                Recipe::StaticCollider { pose, collider, .. } => {
+                    static_shapes.push((StaticShape::of_collider(pose.0, collider), source));
+                }
+                Recipe::PressureSwitch { target, .. } => {
+                    if !self.sources.iter().any(|other| other.id == *target) {
+                        issues.push(SceneIssue::warning(
+                            Some(source.clone()),
+                            IssueKind::UnknownTarget(*target),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (i, (shape, source)) in static_shapes.iter().enumerate() {
+            for (other_shape, other) in &static_shapes[..i] {
+                if shape.overlaps(other_shape) {
+                    issues.push(SceneIssue::warning(
+                        Some((*source).clone()),
+                        IssueKind::OverlappingStaticGeometry {
+                            other: (*other).clone(),
+                        },
+                    ));
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// Check that a polygon collider can be split into convex pieces.
+fn check_polygon(collider: &TiledCollider) -> Option<IssueKind> {
+    let points = collider.polygon.as_ref()?;
+    match polygon::convex_decomposition(points) {
+        Ok(_) => None,
+        Err(PolygonError::TooFewPoints(points)) => Some(IssueKind::DegenerateShape { points }),
+        Err(PolygonError::SelfIntersecting) => Some(IssueKind::SelfIntersectingPolygon),
+    }
+}
+
+/// Static geometry in world space, split into convex pieces for checking overlaps.
+struct StaticShape {
+    pieces: Vec<ConvexPiece>,
+    bounds: Aabb,
+}
+
+/// Convex polygon rounded by `radius`.
+/// Circles are a single point and capsules a segment.
+struct ConvexPiece {
+    points: Vec<sf::Vec2>,
+    radius: f64,
+}
+
+impl StaticShape {
+    fn new(pose: sf::Pose, local_pieces: Vec<ConvexPiece>) -> Self {
+        let pieces: Vec<ConvexPiece> = local_pieces
+            .into_iter()
+            .filter(|piece| !piece.points.is_empty())
+            .map(|piece| ConvexPiece {
+                points: piece.points.iter().map(|p| pose * *p).collect(),
+                radius: piece.radius,
+            })
+            .collect();
+        let mut bounds = Aabb::empty();
+        for piece in &pieces {
+            for p in &piece.points {
+                bounds.extend(*p, piece.radius);
+            }
+        }
+        Self { pieces, bounds }
+    }
+
+    fn of_collider(pose: sf::Pose, collider: &TiledCollider) -> Self {
+        let pieces = match &collider.polygon {
+            // static polygons are built as an outline around this same area
+            Some(points) => polygon::convex_decomposition(points)
+                .unwrap_or_else(|_| vec![polygon::convex_hull(points)])
+                .into_iter()
+                .map(|points| ConvexPiece {
+                    points,
+                    radius: 0.0,
+                })
+                .collect(),
+            None => {
+                let (hw, hh) = (collider.width / 2.0, collider.height / 2.0);
+                let piece = match collider.shape {
+                    TiledColliderShape::Rect => ConvexPiece {
+                        points: vec![
+                            sf::Vec2::new(-hw, -hh),
+                            sf::Vec2::new(hw, -hh),
+                            sf::Vec2::new(hw, hh),
+                            sf::Vec2::new(-hw, hh),
+                        ],
+                        radius: 0.0,
+                    },
+                    TiledColliderShape::Capsule => ConvexPiece {
+                        points: vec![sf::Vec2::new(-hw, 0.0), sf::Vec2::new(hw, 0.0)],
+                        radius: hh,
+                    },
+                    // hexagons and triangles are close enough to their bounding circle
+                    // for the purposes of a warning
+                    TiledColliderShape::Circle
+                    | TiledColliderShape::Hexagon
+                    | TiledColliderShape::Triangle => ConvexPiece {
+                        points: vec![sf::Vec2::zero()],
+                        radius: hw,
+                    },
+                };
+                vec![piece]
+            }
+        };
+        Self::new(pose, pieces)
+    }
+
+    fn of_capsule_chain(pose: sf::Pose, polyline: &[sf::Vec2], thickness: f64) -> Self {
+        // capsule chains ignore the rotation of their pose
+        let pose = sf::Pose::new(pose.translation, sf::Angle::Rad(0.0).into());
+        let pieces = polyline
+            .windows(2)
+            .map(|seg| ConvexPiece {
+                points: seg.to_vec(),
+                radius: thickness / 2.0,
+            })
+            .collect();
+        Self::new(pose, pieces)
+    }
+
+    fn overlaps(&self, other: &Self) -> bool {
+        self.bounds.overlaps(&other.bounds)
+            && self
+                .pieces
+                .iter()
+                .any(|piece| other.pieces.iter().any(|other| piece.overlaps(other)))
+    }
+}
+
+impl ConvexPiece {
+    /// Check if two pieces overlap by more than `OVERLAP_TOLERANCE`.
+    ///
+    /// Separating axis test over edge normals and directions between vertices.
+    /// One of these is the direction of the shortest distance between the pieces
+    /// if they're apart, and of the smallest penetration if they overlap.
+    fn overlaps(&self, other: &Self) -> bool {
+        let vertex_axes = self
+            .points
+            .iter()
+            .flat_map(|&p| other.points.iter().map(move |&q| q - p));
+        let separation = self
+            .edge_normals()
+            .chain(other.edge_normals())
+            .chain(vertex_axes)
+            .filter(|axis| axis.mag_sq() > f64::EPSILON)
+            .map(|axis| {
+                let axis = axis.normalized();
+                let (min, max) = self.project(axis);
+                let (other_min, other_max) = other.project(axis);
+                (other_min - max).max(min - other_max)
+            })
+            .fold(f64::MIN, f64::max);
+        separation - self.radius - other.radius < -OVERLAP_TOLERANCE
+    }
+
+    fn edge_normals(&self) -> impl Iterator<Item = sf::Vec2> + '_ {
+        let n = self.points.len();
+        (0..n).map(move |i| {
+            let edge = self.points[(i + 1) % n] - self.points[i];
+            sf::Vec2::new(-edge.y, edge.x)
+        })
+    }
+
+    fn project(&self, axis: sf::Vec2) -> (f64, f64) {
+        self.points
+            .iter()
+            .map(|p| p.dot(axis))
+            .fold((f64::MAX, f64::MIN), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    }
+}
+
+/// Axis-aligned bounding box to skip the exact overlap check for far apart shapes.
+#[derive(Clone, Copy, Debug)]
+struct Aabb {
+    min: sf::Vec2,
+    max: sf::Vec2,
+}
+
+impl Aabb {
+    fn empty() -> Self {
+        Self {
+            min: sf::Vec2::broadcast(f64::MAX),
+            max: sf::Vec2::broadcast(f64::MIN),
+        }
+    }
+
+    /// Grow the box to contain a circle.
+    fn extend(&mut self, center: sf::Vec2, radius: f64) {
+        let r = sf::Vec2::broadcast(radius);
+        self.min = self.min.min_by_component(center - r);
+        self.max = self.max.max_by_component(center + r);
+    }
+
+    fn overlaps(&self, other: &Self) -> bool {
+        self.min.x + OVERLAP_TOLERANCE < other.max.x
+            && other.min.x + OVERLAP_TOLERANCE < self.max.x
+            && self.min.y + OVERLAP_TOLERANCE < other.max.y
+            && other.min.y + OVERLAP_TOLERANCE < self.max.y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    /// Scene with the given recipes, each coming from an object with id 1, 2, 3...
+    fn scene_with(recipes: Value) -> Scene {
+        let recipes: Vec<Recipe> = serde_json::from_value(recipes).unwrap();
+        let sources = (1..=recipes.len() as u32)
+            .map(|id| ObjectRef {
+                id,
+                ..Default::default()
+            })
+            .collect();
+        Scene {
+            recipes,
+            sources,
+            ..Default::default()
+        }
+    }
+
+    fn spawn_point() -> Value {
+        json!({ "type": "PlayerSpawnPoint", "pose": pose(0.0, 0.0, 0.0), "id": 100 })
+    }
+
+    fn pose(x: f64, y: f64, rotation: f64) -> Value {
+        json!({ "x": x, "y": y, "rotation": rotation })
+    }
+
+    fn rect(x: f64, y: f64, rotation: f64, width: f64, height: f64) -> Value {
+        json!({
+            "type": "StaticCollider",
+            "pose": pose(x, y, rotation),
+            "width": width,
+            "height": height,
+        })
+    }
+
+    /// Ids of the objects with overlap warnings.
+    fn overlapping(issues: &[SceneIssue]) -> Vec<(u32, u32)> {
+        issues
+            .iter()
+            .filter_map(|issue| match &issue.kind {
+                IssueKind::OverlappingStaticGeometry { other } => {
+                    Some((issue.object.as_ref().unwrap().id, other.id))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn valid_scene_has_no_issues() {
+        let scene = scene_with(json!([spawn_point(), rect(0.0, -1.0, 0.0, 4.0, 1.0)]));
+        let issues = scene.validate();
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+
+    #[test]
+    fn spawn_points_are_checked() {
+        let issues = scene_with(json!([])).validate();
+        assert!(matches!(
+            issues[..],
+            [SceneIssue {
+                kind: IssueKind::NoSpawnPoint,
+                ..
+            }]
+        ));
+
+        let issues = scene_with(json!([spawn_point(), spawn_point()])).validate();
+        assert!(matches!(
+            issues[..],
+            [SceneIssue {
+                kind: IssueKind::AmbiguousStartPoint(2),
+                ..
+            }]
+        ));
+    }
+
+    #[test]
+    fn overlapping_static_geometry_is_reported() {
+        let scene = scene_with(json!([
+            spawn_point(),
+            rect(0.0, 0.0, 0.0, 2.0, 2.0),
+            // overlaps the first one
+            rect(1.5, 0.0, 0.0, 2.0, 2.0),
+            // flush against the first one
+            rect(0.0, 2.0, 0.0, 2.0, 2.0),
+        ]));
+        assert_eq!(overlapping(&scene.validate()), vec![(3, 2)]);
+    }
+
+    #[test]
+    fn rotated_shapes_are_checked_exactly() {
+        // the diamond's bounding box overlaps the corner of the square, but the diamond doesn't
+        let scene = scene_with(json!([
+            spawn_point(),
+            rect(0.0, 0.0, 0.0, 2.0, 2.0),
+            rect(2.2, 2.2, std::f64::consts::FRAC_PI_4, 2.0, 2.0),
+        ]));
+        assert!(overlapping(&scene.validate()).is_empty());
+
+        let scene = scene_with(json!([
+            spawn_point(),
+            rect(0.0, 0.0, 0.0, 2.0, 2.0),
+            rect(1.8, 0.0, std::f64::consts::FRAC_PI_4, 2.0, 2.0),
+        ]));
+        assert_eq!(overlapping(&scene.validate()), vec![(3, 2)]);
+    }
+
+    #[test]
+    fn capsule_chains_are_checked_for_overlaps() {
+        let chain = |y: f64| {
+            json!({
+                "type": "StaticCapsuleChain",
+                "pose": pose(0.0, y, 0.0),
+                "polyline": [{ "x": -5.0, "y": 0.0 }, { "x": 5.0, "y": 0.0 }],
+                "thickness": 0.5,
+            })
+        };
+        let scene = scene_with(json!([
+            spawn_point(),
+            rect(0.0, 0.0, 0.0, 2.0, 2.0),
+            chain(1.0)
+        ]));
+        assert_eq!(overlapping(&scene.validate()), vec![(3, 2)]);
+        let scene = scene_with(json!([
+            spawn_point(),
+            rect(0.0, 0.0, 0.0, 2.0, 2.0),
+            chain(1.3)
+        ]));
+        assert!(overlapping(&scene.validate()).is_empty());
+    }
+
+    #[test]
+    fn polygons_are_checked_on_every_shape() {
+        let scene = scene_with(json!([
+            spawn_point(),
+            {
+                "type": "PhysicsObject",
+                "pose": pose(0.0, 0.0, 0.0),
+                "width": 0.0,
+                "height": 0.0,
+                "polygon": [{ "x": 0.0, "y": 0.0 }, { "x": 1.0, "y": 0.0 }],
+            },
+            {
+                "type": "Weed",
+                "pose": pose(0.0, 0.0, 0.0),
+                "width": 0.0,
+                "height": 0.0,
+                "polygon": [
+                    { "x": 0.0, "y": 0.0 },
+                    { "x": 1.0, "y": 1.0 },
+                    { "x": 1.0, "y": 0.0 },
+                    { "x": 0.0, "y": 1.0 },
+                ],
+            },
+        ]));
+        let issues = scene.validate();
+        assert!(matches!(
+            issues[..],
+            [
+                SceneIssue {
+                    kind: IssueKind::DegenerateShape { points: 2 },
+                    ..
+                },
+                SceneIssue {
+                    kind: IssueKind::SelfIntersectingPolygon,
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn unknown_materials_are_errors() {
+        let scene = scene_with(json!([
+            spawn_point(),
+            {
+                "type": "PhysicsObject",
+                "pose": pose(0.0, 0.0, 0.0),
+                "width": 1.0,
+                "height": 1.0,
+                "material": "wood",
+            },
+            {
+                "type": "PhysicsObject",
+                "pose": pose(2.0, 0.0, 0.0),
+                "width": 1.0,
+                "height": 1.0,
+                "material": "unobtainium",
+            },
+        ]));
+        let materials: FireMaterials = serde_json::from_value(json!({ "wood": {} })).unwrap();
+        let issues = scene.validate_with_materials(Some(&materials));
+        assert_eq!(issues.len(), 1);
+        assert!(issues[0].is_error());
+        assert!(
+            matches!(&issues[0].kind, IssueKind::UnknownMaterial(name) if name == "unobtainium")
+        );
+    }
+
+    #[test]
+    fn zero_length_segments_and_unknown_targets_are_reported() {
+        let scene = scene_with(json!([
+            spawn_point(),
+            {
+                "type": "StaticCapsuleChain",
+                "pose": pose(0.0, 0.0, 0.0),
+                "polyline": [{ "x": 0.0, "y": 0.0 }, { "x": 0.0, "y": 0.0 }, { "x": 1.0, "y": 0.0 }],
+                "thickness": 0.5,
+            },
+            {
+                "type": "PressureSwitch",
+                "pose": pose(5.0, 0.0, 0.0),
+                "width": 1.0,
+                "height": 0.2,
+                "target": 42,
+            },
+        ]));
+        let issues = scene.validate();
+        assert!(matches!(
+            issues[..],
+            [
+                SceneIssue {
+                    kind: IssueKind::ZeroLengthSegment { index: 0 },
+                    ..
+                },
+                SceneIssue {
+                    kind: IssueKind::UnknownTarget(42),
+                    ..
+                },
+            ]
+        ));
+    }
+
+    #[test]
+    fn burn_goal_without_targets_is_an_error() {
+        let mut scene = scene_with(json!([spawn_point(), rect(0.0, 0.0, 0.0, 2.0, 2.0)]));
+        scene.goal = GoalKind::BurnAllTargets;
+        let issues = scene.validate();
+        assert!(matches!(
+            issues[..],
+            [SceneIssue {
+                kind: IssueKind::NoBurnTargets,
+                ..
+            }]
+        ));
+        assert!(issues[0].is_error());
+
+        let mut target = rect(0.0, 0.0, 0.0, 2.0, 2.0);
+        target["burn_target"] = json!(true);
+        let mut scene = scene_with(json!([spawn_point(), target]));
+        scene.goal = GoalKind::BurnAllTargets;
+        assert!(scene.validate().is_empty());
+    }
+
+    #[test]
+    fn empty_vine_budgets_are_reported() {
+        let budget_issues = |max_vines, max_vine_length| {
+            let scene = Scene {
+                max_vines,
+                max_vine_length,
+                ..scene_with(json!([spawn_point()]))
+            };
+            scene
+                .validate()
+                .into_iter()
+                .filter_map(|issue| match issue.kind {
+                    IssueKind::EmptyVineBudget { property } => Some(property),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert!(budget_issues(None, None).is_empty());
+        assert!(budget_issues(Some(1), Some(0.5)).is_empty());
+        assert_eq!(budget_issues(Some(0), None), vec!["max_vines"]);
+        assert_eq!(budget_issues(None, Some(0.0)), vec!["max_vine_length"]);
+        assert_eq!(
+            budget_issues(Some(0), Some(-3.0)),
+            vec!["max_vines", "max_vine_length"]
+        );
+    }
+}
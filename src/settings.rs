@@ -1,29 +1,148 @@
+use std::fmt;
+
 use assets_manager::{loader, Asset};
+use gilrs::{Axis, Button};
+use serde_json::{Map, Value};
 use starframe::input::{Key, MouseButton};
 
-#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Settings {
     pub keymap: Keymap,
 }
 
-// For now, treating settings as an asset.
-// TODO: this should eventually be modifiable in-game
-// and written in a standard location.
-// Probably figure this out when implementing save files
+// Default settings are an asset,
+// the user's changes to them are layered on top with `SettingsOverrides`
 impl Asset for Settings {
     const EXTENSION: &'static str = "json";
 
     type Loader = loader::JsonLoader;
 }
 
-#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+/// The user's changes to the default settings.
+///
+/// Only changed fields are stored, in the same shape as `Settings`,
+/// so changes to the defaults still apply to everything the user hasn't touched.
+/// Saved in the user's config directory, see `save.rs`.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct SettingsOverrides {
+    #[serde(flatten)]
+    overrides: Map<String, Value>,
+    // the defaults the overrides were last applied to and the result
+    #[serde(skip)]
+    cache: Option<(Settings, Settings)>,
+}
+
+impl SettingsOverrides {
+    /// Get the settings with the overrides applied on top of the given defaults.
+    pub fn apply(&mut self, defaults: &Settings) -> Settings {
+        if let Some((cached_defaults, applied)) = &self.cache {
+            if cached_defaults == defaults {
+                return *applied;
+            }
+        }
+
+        let mut settings = serde_json::to_value(defaults).expect("settings are always valid json");
+        merge_json(&mut settings, &self.overrides);
+        let applied = serde_json::from_value(settings).unwrap_or_else(|err| {
+            eprintln!("invalid settings overrides, using defaults: {err}");
+            *defaults
+        });
+
+        self.cache = Some((*defaults, applied));
+        applied
+    }
+
+    /// Bind an action to a different key or mouse button.
+    ///
+    /// Fails if the binding is the wrong kind for the action,
+    /// e.g. a keyboard key for an action that uses mouse buttons.
+    pub fn rebind(&mut self, action: Action, binding: Binding) -> Result<(), RebindError> {
+        let value = match (action.uses_mouse(), binding) {
+            (false, Binding::Key(key)) => serde_json::to_value(key),
+            (true, Binding::Mouse(button)) => serde_json::to_value(button),
+            _ => return Err(RebindError::WrongKind { action, binding }),
+        }
+        .expect("bindings are always valid json");
+
+        let (group, name) = action.path();
+        let group = self
+            .overrides
+            .entry("keymap")
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .and_then(|keymap| {
+                keymap
+                    .entry(group)
+                    .or_insert_with(|| Value::Object(Map::new()))
+                    .as_object_mut()
+            })
+            .ok_or(RebindError::InvalidOverrides)?;
+        group.insert(name.into(), value);
+
+        self.cache = None;
+        Ok(())
+    }
+
+    /// Go back to the default binding for an action.
+    pub fn reset_binding(&mut self, action: Action) {
+        let (group, name) = action.path();
+        if let Some(group) = self
+            .overrides
+            .get_mut("keymap")
+            .and_then(|keymap| keymap.get_mut(group))
+            .and_then(|group| group.as_object_mut())
+        {
+            group.remove(name);
+        }
+        self.cache = None;
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum RebindError {
+    WrongKind {
+        action: Action,
+        binding: Binding,
+    },
+    /// The keymap in the overrides file isn't an object.
+    InvalidOverrides,
+}
+
+impl fmt::Display for RebindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongKind { action, binding } => {
+                write!(f, "{binding} can't be used for {action}")
+            }
+            Self::InvalidOverrides => write!(f, "keymap in settings overrides is malformed"),
+        }
+    }
+}
+
+/// Recursively overwrite the fields of `target` with the ones in `overrides`.
+fn merge_json(target: &mut Value, overrides: &Map<String, Value>) {
+    let Value::Object(target) = target else { return };
+    for (key, value) in overrides {
+        match (target.get_mut(key), value) {
+            (Some(target_value @ Value::Object(_)), Value::Object(value)) => {
+                merge_json(target_value, value);
+            }
+            _ => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct Keymap {
     pub menus: MenuKeys,
     pub player: PlayerKeys,
+    pub gamepad: GamepadKeys,
     pub debug: DebugKeys,
 }
 
-#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct MenuKeys {
     pub exit: Key,
     // Temporary key to quickly reload the level.
@@ -32,25 +151,309 @@ pub struct MenuKeys {
     pub pause: Key,
 }
 
-#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct PlayerKeys {
     pub right: Key,
     pub left: Key,
     pub up: Key,
     pub down: Key,
     pub jump: Key,
-    /// Aim a vine by holding, shoot by releasing.
+    /// Aim and shoot a vine, exact behavior depends on `aim_mode`.
     pub shoot: MouseButton,
-    /// Delay before slowing down time for aiming.
+    pub aim_mode: AimMode,
+    /// Delay before slowing down time for aiming in `AimMode::Hold`.
     pub aim_delay: usize,
+    /// Speed of time while aiming, 1.0 being normal speed.
+    pub aim_time_scale: f64,
     /// Cancel out of aim mode (holding down LMB by default) without shooting.
     pub cancel_aim: MouseButton,
-    /// Pull back / destroy the vine currently held.
+    /// Start reeling in the vine currently held.
+    /// Pressing again while it's reeling in lets go of the vine instead.
     pub retract_vine: MouseButton,
+    /// Remove the vine being aimed at, or the oldest one if not aiming at any.
+    pub dissolve_vine: Key,
+    /// Pick two points to connect with a vine without holding on to it.
+    pub tether: Key,
+    /// Pick up and put down torches.
+    pub interact: Key,
     pub respawn: Key,
 }
 
-#[derive(Clone, Copy, Debug, serde::Deserialize, serde::Serialize)]
+/// How the shoot button enters aim mode (slowing down time) and shoots vines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum AimMode {
+    /// Hold to aim, release to shoot. Tap to shoot without aiming.
+    Hold,
+    /// Press once to aim, again to shoot.
+    Toggle,
+    /// Shoot immediately on press, never slow down time.
+    Instant,
+}
+
+/// Gamepad controls for the player, used alongside the keyboard and mouse.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct GamepadKeys {
+    pub move_x: Axis,
+    pub move_y: Axis,
+    pub aim_x: Axis,
+    pub aim_y: Axis,
+    /// Stick tilt below this (between 0 and 1) is ignored.
+    pub deadzone: f64,
+    pub jump: Button,
+    /// Works the same as the mouse button.
+    pub shoot: Button,
+    pub cancel_aim: Button,
+    pub retract_vine: Button,
+    pub dissolve_vine: Button,
+    pub tether: Button,
+    pub interact: Button,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct DebugKeys {
     pub toggle_grid: Key,
+    /// Go through every action and rebind it from the console.
+    /// Temporary until we have menus.
+    pub rebind: Key,
+}
+
+//
+// rebinding
+//
+
+/// A key or mouse button bound to an action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{key:?}"),
+            Self::Mouse(button) => write!(f, "mouse {button:?}"),
+        }
+    }
+}
+
+/// Every action that can be rebound,
+/// corresponding to the keyboard and mouse fields of `Keymap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Exit,
+    Reload,
+    Pause,
+    Right,
+    Left,
+    Up,
+    Down,
+    Jump,
+    Shoot,
+    CancelAim,
+    RetractVine,
+    DissolveVine,
+    Tether,
+    Interact,
+    Respawn,
+    ToggleGrid,
+    Rebind,
+}
+
+impl Action {
+    pub const ALL: [Action; 17] = [
+        Action::Exit,
+        Action::Reload,
+        Action::Pause,
+        Action::Right,
+        Action::Left,
+        Action::Up,
+        Action::Down,
+        Action::Jump,
+        Action::Shoot,
+        Action::CancelAim,
+        Action::RetractVine,
+        Action::DissolveVine,
+        Action::Tether,
+        Action::Interact,
+        Action::Respawn,
+        Action::ToggleGrid,
+        Action::Rebind,
+    ];
+
+    /// Group and field name of the action in the keymap, e.g. `("player", "jump")`.
+    pub fn path(self) -> (&'static str, &'static str) {
+        match self {
+            Action::Exit => ("menus", "exit"),
+            Action::Reload => ("menus", "reload"),
+            Action::Pause => ("menus", "pause"),
+            Action::Right => ("player", "right"),
+            Action::Left => ("player", "left"),
+            Action::Up => ("player", "up"),
+            Action::Down => ("player", "down"),
+            Action::Jump => ("player", "jump"),
+            Action::Shoot => ("player", "shoot"),
+            Action::CancelAim => ("player", "cancel_aim"),
+            Action::RetractVine => ("player", "retract_vine"),
+            Action::DissolveVine => ("player", "dissolve_vine"),
+            Action::Tether => ("player", "tether"),
+            Action::Interact => ("player", "interact"),
+            Action::Respawn => ("player", "respawn"),
+            Action::ToggleGrid => ("debug", "toggle_grid"),
+            Action::Rebind => ("debug", "rebind"),
+        }
+    }
+
+    /// Whether the action is bound to a mouse button instead of a key.
+    pub fn uses_mouse(self) -> bool {
+        matches!(
+            self,
+            Action::Shoot | Action::CancelAim | Action::RetractVine
+        )
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (group, name) = self.path();
+        write!(f, "{group}.{name}")
+    }
+}
+
+impl Keymap {
+    pub fn binding(&self, action: Action) -> Binding {
+        match action {
+            Action::Exit => Binding::Key(self.menus.exit),
+            Action::Reload => Binding::Key(self.menus.reload),
+            Action::Pause => Binding::Key(self.menus.pause),
+            Action::Right => Binding::Key(self.player.right),
+            Action::Left => Binding::Key(self.player.left),
+            Action::Up => Binding::Key(self.player.up),
+            Action::Down => Binding::Key(self.player.down),
+            Action::Jump => Binding::Key(self.player.jump),
+            Action::Shoot => Binding::Mouse(self.player.shoot),
+            Action::CancelAim => Binding::Mouse(self.player.cancel_aim),
+            Action::RetractVine => Binding::Mouse(self.player.retract_vine),
+            Action::DissolveVine => Binding::Key(self.player.dissolve_vine),
+            Action::Tether => Binding::Key(self.player.tether),
+            Action::Interact => Binding::Key(self.player.interact),
+            Action::Respawn => Binding::Key(self.player.respawn),
+            Action::ToggleGrid => Binding::Key(self.debug.toggle_grid),
+            Action::Rebind => Binding::Key(self.debug.rebind),
+        }
+    }
+
+    /// Other actions bound to the same key or button as the given one.
+    pub fn conflicts(&self, action: Action) -> Vec<Action> {
+        let binding = self.binding(action);
+        Action::ALL
+            .into_iter()
+            .filter(|&other| other != action && self.binding(other) == binding)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn defaults() -> Settings {
+        serde_json::from_str(include_str!("../assets/settings.json")).unwrap()
+    }
+
+    #[test]
+    fn merge_json_overwrites_nested_fields() {
+        let mut target = json!({ "a": 1, "b": { "c": 2, "d": 3 }, "e": { "f": 4 } });
+        let overrides = json!({ "b": { "c": 5 }, "e": 6, "g": 7 });
+        merge_json(&mut target, overrides.as_object().unwrap());
+        assert_eq!(
+            target,
+            json!({ "a": 1, "b": { "c": 5, "d": 3 }, "e": 6, "g": 7 })
+        );
+    }
+
+    #[test]
+    fn overrides_only_change_what_they_contain() {
+        let defaults = defaults();
+        let mut overrides = SettingsOverrides::default();
+        assert_eq!(overrides.apply(&defaults), defaults);
+
+        overrides
+            .rebind(Action::Jump, Binding::Key(Key::K))
+            .unwrap();
+        let settings = overrides.apply(&defaults);
+        assert_eq!(settings.keymap.player.jump, Key::K);
+        assert_eq!(settings.keymap.player.left, defaults.keymap.player.left);
+
+        overrides.reset_binding(Action::Jump);
+        assert_eq!(overrides.apply(&defaults), defaults);
+    }
+
+    #[test]
+    fn invalid_overrides_fall_back_to_defaults() {
+        let defaults = defaults();
+        let mut overrides: SettingsOverrides =
+            serde_json::from_value(json!({ "keymap": { "player": { "jump": "NotAKey" } } }))
+                .unwrap();
+        assert_eq!(overrides.apply(&defaults), defaults);
+    }
+
+    #[test]
+    fn every_action_is_in_the_keymap() {
+        let keymap = serde_json::to_value(defaults().keymap).unwrap();
+        for action in Action::ALL {
+            let (group, name) = action.path();
+            assert!(keymap[group][name].is_string(), "{action} not found");
+        }
+    }
+
+    #[test]
+    fn conflicts_are_found_both_ways() {
+        let keymap = defaults().keymap;
+        assert_eq!(
+            keymap.conflicts(Action::CancelAim),
+            vec![Action::RetractVine]
+        );
+        assert_eq!(
+            keymap.conflicts(Action::RetractVine),
+            vec![Action::CancelAim]
+        );
+        assert_eq!(keymap.conflicts(Action::Reload), vec![Action::Respawn]);
+        assert!(keymap.conflicts(Action::Jump).is_empty());
+    }
+
+    #[test]
+    fn rebinding_changes_conflicts() {
+        let defaults = defaults();
+        let mut overrides = SettingsOverrides::default();
+
+        let left = Binding::Key(defaults.keymap.player.left);
+        overrides.rebind(Action::Jump, left).unwrap();
+        let keymap = overrides.apply(&defaults).keymap;
+        assert_eq!(keymap.conflicts(Action::Jump), vec![Action::Left]);
+
+        overrides
+            .rebind(Action::Respawn, Binding::Key(Key::G))
+            .unwrap();
+        let keymap = overrides.apply(&defaults).keymap;
+        assert!(keymap.conflicts(Action::Reload).is_empty());
+    }
+
+    #[test]
+    fn bindings_must_be_the_right_kind() {
+        let mut overrides = SettingsOverrides::default();
+        assert!(matches!(
+            overrides.rebind(Action::Jump, Binding::Mouse(MouseButton::Left)),
+            Err(RebindError::WrongKind { .. })
+        ));
+        assert!(matches!(
+            overrides.rebind(Action::Shoot, Binding::Key(Key::Space)),
+            Err(RebindError::WrongKind { .. })
+        ));
+        assert!(overrides
+            .rebind(Action::Shoot, Binding::Mouse(MouseButton::Left))
+            .is_ok());
+    }
 }